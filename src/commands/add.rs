@@ -1,31 +1,73 @@
-use crate::moree_state_manager::{self, PackageState};
-use crate::package_manager_integration;
 use crate::utils;
+use chrono::Utc;
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use moree::error::{self, Error};
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::{Backends, InstalledPackage, PackageManager};
 use std::collections::HashMap;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::Path;
+
+// An install reason given up front, e.g. with `--explicit --memo`, instead of through
+// the prompts.
+pub struct Decision {
+    pub explicit: bool,
+    pub memo: Option<String>,
+    pub tags: Vec<String>,
+}
 
 pub fn run(
     package_states: &mut HashMap<String, PackageState>,
-    state_file_path: &PathBuf,
+    state_file_path: &Path,
+    backends: &Backends,
     packages: &[String],
-) -> io::Result<()> {
-    let packages_to_process = get_packages_to_process(packages)?;
+    decision: Option<&Decision>,
+) -> error::Result<()> {
+    let packages_to_process = get_packages_to_process(backends, packages)?;
+
+    if let Some(decision) = decision {
+        if !decision.explicit {
+            let refused: Vec<String> = packages_to_process
+                .iter()
+                .filter_map(|(backend, package_name)| {
+                    refuse_dependency(backends, backend.namespace(), package_name)
+                })
+                .collect();
+            if !refused.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, refused.join("\n")).into());
+            }
+        }
+        for (backend, package_name) in packages_to_process {
+            record_decision(package_states, backend.namespace(), &package_name, decision);
+            println!(
+                "{} {}",
+                backends.label(backend.namespace(), &package_name),
+                if decision.explicit {
+                    "recorded as explicitly installed".green()
+                } else {
+                    "recorded as a dependency".yellow()
+                }
+            );
+        }
+        moree_state_manager::save_package_states(state_file_path, package_states)?;
+        return Ok(());
+    }
+
     let mut should_save = true; // Flag to track whether to save
 
-    for package_name in packages_to_process {
-        if package_states
-            .get(&package_name)
-            .map_or(false, |p| p.explicit)
-        {
+    for (backend, package_name) in packages_to_process {
+        let key = moree_state_manager::qualified_name(backend.namespace(), &package_name);
+        if package_states.get(&key).is_some_and(|p| p.explicit) {
             continue;
         }
 
-        match handle_package_interactively(package_states, &package_name) {
+        utils::require_terminal(
+            "use --explicit or --dep to record packages without prompts, or --from to read decisions from a file",
+        )?;
+        match handle_package_interactively(package_states, backends, backend, &package_name) {
             Ok(_) => {} // Continue to the next package
-            Err(e) if e.to_string() == "Quit" => {
+            Err(Error::UserAborted) => {
                 should_save = utils::confirm_prompt("Save changes before quitting? (Y/n)", true)?;
                 break; // Exit the loop
             }
@@ -33,7 +75,6 @@ pub fn run(
         }
     }
 
-    println!("{}", &should_save);
     if should_save {
         moree_state_manager::save_package_states(state_file_path, package_states)?;
     }
@@ -41,75 +82,340 @@ pub fn run(
     Ok(())
 }
 
-fn get_packages_to_process(packages: &[String]) -> io::Result<Vec<String>> {
+// Records decisions read from a file, or from stdin for `-`, one package per line:
+// `firefox explicit "memo"` or `gtk3 dep`. Every line is checked before anything is
+// recorded; the accepted ones are saved together and the rejected ones reported.
+pub fn run_from(
+    package_states: &mut HashMap<String, PackageState>,
+    state_file_path: &Path,
+    backends: &Backends,
+    source: &Path,
+) -> error::Result<()> {
+    let content = if source == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        fs::read_to_string(source).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("Could not read {}: {}", source.display(), e),
+            )
+        })?
+    };
+
+    let index = backends.installed_index()?;
+    let mut accepted = Vec::new();
+    let mut rejected = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+
+    for (line_number, line) in (1..).zip(content.lines()) {
+        let (package, decision) = match parse_decision_line(line) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(reason) => {
+                rejected.push((line_number, reason));
+                continue;
+            }
+        };
+        let (backend, package_name) = match index.locate(&package) {
+            Ok(Some(installed)) => installed,
+            Ok(None) => {
+                rejected.push((line_number, format!("{} is not installed", package)));
+                continue;
+            }
+            Err(e) => {
+                rejected.push((line_number, e.to_string()));
+                continue;
+            }
+        };
+
+        if !decision.explicit {
+            if let Some(reason) = refuse_dependency(backends, backend.namespace(), &package_name) {
+                rejected.push((line_number, reason));
+                continue;
+            }
+        }
+
+        let key = moree_state_manager::qualified_name(backend.namespace(), &package_name);
+        if let Some(first) = seen.insert(key, line_number) {
+            rejected.push((
+                line_number,
+                format!("{} is already listed on line {}", package, first),
+            ));
+            continue;
+        }
+        accepted.push((backend, package_name, decision));
+    }
+
+    for (backend, package_name, decision) in &accepted {
+        record_decision(package_states, backend.namespace(), package_name, decision);
+    }
+    if !accepted.is_empty() {
+        moree_state_manager::save_package_states(state_file_path, package_states)?;
+    }
+    println!("Recorded {} packages.", accepted.len());
+
+    if rejected.is_empty() {
+        return Ok(());
+    }
+    for (line_number, reason) in &rejected {
+        eprintln!("{} line {}: {}", "Rejected".red(), line_number, reason);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} lines were rejected", rejected.len()),
+    )
+    .into())
+}
+
+// Parses `package explicit|dep ["memo"]`. Blank lines and `#` comments yield nothing.
+fn parse_decision_line(line: &str) -> Result<Option<(String, Decision)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let (package, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let rest = rest.trim_start();
+    let (reason, memo) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let explicit = match reason {
+        "explicit" => true,
+        "dep" => false,
+        "" => {
+            return Err(format!(
+                "missing reason for {}; expected explicit or dep",
+                package
+            ))
+        }
+        _ => {
+            return Err(format!(
+                "unknown reason '{}'; expected explicit or dep",
+                reason
+            ))
+        }
+    };
+
+    let memo = memo.trim();
+    let memo = if memo.is_empty() {
+        None
+    } else {
+        Some(parse_quoted(memo)?)
+    };
+
+    Ok(Some((
+        package.to_string(),
+        Decision {
+            explicit,
+            memo,
+            tags: Vec::new(),
+        },
+    )))
+}
+
+// Unquotes a memo written as `"text"`, where `\"` and `\\` stand for `"` and `\`.
+fn parse_quoted(text: &str) -> Result<String, String> {
+    let Some(inner) = text.strip_prefix('"') else {
+        return Err(format!("the memo must be quoted: {}", text));
+    };
+
+    let mut memo = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped @ ('"' | '\\')) => memo.push(escaped),
+                _ => return Err(format!("invalid escape in memo: {}", text)),
+            },
+            '"' if chars.as_str().trim().is_empty() => return Ok(memo),
+            '"' => return Err(format!("unexpected text after the memo: {}", text)),
+            c => memo.push(c),
+        }
+    }
+    Err(format!("the memo is missing its closing quote: {}", text))
+}
+
+fn get_packages_to_process<'a>(
+    backends: &'a Backends,
+    packages: &[String],
+) -> error::Result<Vec<InstalledPackage<'a>>> {
+    let mut packages_to_process = Vec::new();
+
     if packages.is_empty() {
-        package_manager_integration::get_installed_packages_vec()
+        for backend in backends.iter() {
+            for package_name in backend.list_explicit()? {
+                packages_to_process.push((backend, package_name));
+            }
+        }
     } else {
-        let installed_packages = package_manager_integration::get_installed_packages_hashset()?;
-        let missing_packages: Vec<_> = packages
-            .iter()
-            .filter(|p| !installed_packages.contains(*p))
-            .cloned()
-            .collect();
+        let (found, missing_packages) = backends.find_installed(packages)?;
+        packages_to_process = found;
 
         if !missing_packages.is_empty() {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!(
-                    "The following packages are not installed: {:?}",
-                    missing_packages
-                ),
-            ));
+            return Err(Error::PackageNotInstalled(missing_packages));
         }
-        Ok(packages.to_vec())
     }
+
+    Ok(packages_to_process)
+}
+
+// Records the reason for a package. Without a new memo, an existing one is kept; new
+// tags are added to the existing ones.
+pub fn record_decision(
+    package_states: &mut HashMap<String, PackageState>,
+    namespace: &str,
+    package_name: &str,
+    decision: &Decision,
+) {
+    let key = moree_state_manager::qualified_name(namespace, package_name);
+    let existing = package_states.get(&key);
+    let memo = decision
+        .memo
+        .clone()
+        .filter(|memo| !memo.is_empty())
+        .or_else(|| existing.and_then(|state| state.memo.clone()));
+    let tags = moree_state_manager::normalize_tags(
+        existing
+            .into_iter()
+            .flat_map(|state| state.tags.iter().cloned())
+            .chain(decision.tags.iter().cloned()),
+    );
+    insert_stamped(
+        package_states,
+        key,
+        PackageState {
+            explicit: decision.explicit,
+            memo,
+            tags,
+            ..Default::default()
+        },
+    );
+}
+
+// Why the package cannot be recorded as a dependency, if it cannot: backends without
+// install reasons count every package as explicitly installed.
+pub fn refuse_dependency(
+    backends: &Backends,
+    namespace: &str,
+    package_name: &str,
+) -> Option<String> {
+    let backend = backends.get(namespace)?;
+    if backend.supports_install_reasons() {
+        return None;
+    }
+    Some(format!(
+        "{} cannot be recorded as a dependency; the {} backend has no install reasons",
+        backends.label(namespace, package_name),
+        backend.name()
+    ))
+}
+
+// Inserts a freshly confirmed entry, carrying the timestamps over from the one it
+// replaces.
+pub fn insert_stamped(
+    package_states: &mut HashMap<String, PackageState>,
+    key: String,
+    mut state: PackageState,
+) {
+    state.stamp(package_states.get(&key), Utc::now());
+    package_states.insert(key, state);
+}
+
+pub fn print_package_header(backends: &Backends, namespace: &str, package_name: &str) {
+    println!(
+        "{}",
+        format!("Package: {}", backends.label(namespace, package_name))
+            .bold()
+            .cyan()
+    );
 }
 
 fn handle_package_interactively(
     package_states: &mut HashMap<String, PackageState>,
+    backends: &Backends,
+    backend: &dyn PackageManager,
     package_name: &str,
-) -> io::Result<()> {
-    println!("{}", format!("Package: {}", package_name).bold().cyan());
-    package_manager_integration::display_package_details(package_name)?;
-
-    let options = &["Yes", "No", "Skip", "Quit"]; // Make options a slice.
-    let selection = Select::with_theme(&ColorfulTheme::default())
-        .with_prompt("Did you explicitly install this package?")
-        .items(options)
-        .default(0)
-        .interact()
-        .unwrap();
+) -> error::Result<()> {
+    let namespace = backend.namespace();
+    print_package_header(backends, namespace, package_name);
+    backend.display_package_details(package_name)?;
 
+    // Without install reasons, a package cannot be recorded as a dependency.
+    let options: &[&str] = if backend.supports_install_reasons() {
+        &["Yes", "No", "Skip", "Quit"]
+    } else {
+        &["Yes", "Skip", "Quit"]
+    };
+    let selection = utils::select_prompt("Did you explicitly install this package?", options, 0)?;
+
+    let key = moree_state_manager::qualified_name(namespace, package_name);
     match options[selection] {
         // Direct match
         "Yes" => {
-            let memo: String = Input::new()
-                .with_prompt("Why did you install this package? (optional)")
-                .allow_empty(true)
-                .interact_text()
+            let memo = utils::input_prompt("Why did you install this package? (optional)", "")?;
+            let current_tags = package_states
+                .get(&key)
+                .map(|state| state.tags.join(", "))
                 .unwrap_or_default();
+            let tags = utils::input_prompt("Tags, separated by commas (optional)", &current_tags)?;
 
-            package_states.insert(
-                package_name.to_string(),
+            insert_stamped(
+                package_states,
+                key,
                 PackageState {
                     explicit: true,
                     memo: Some(memo).filter(|s| !s.is_empty()), // More concise
+                    tags: moree_state_manager::parse_tags(&tags),
+                    ..Default::default()
                 },
             );
         }
         "No" => {
-            package_states.insert(
-                package_name.to_string(),
+            insert_stamped(
+                package_states,
+                key,
                 PackageState {
                     explicit: false,
-                    memo: None,
+                    ..Default::default()
                 },
             );
         }
         "Skip" => {}
-        "Quit" => return Err(io::Error::new(io::ErrorKind::Other, "Quit")), // Clean return
+        "Quit" => return Err(Error::UserAborted),
         _ => unreachable!(), // This shouldn't be possible given the Select.
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> (String, bool, Option<String>) {
+        let (package, decision) = parse_decision_line(line).unwrap().unwrap();
+        (package, decision.explicit, decision.memo)
+    }
+
+    #[test]
+    fn parses_decision_lines() {
+        assert_eq!(parse("gtk3 dep"), ("gtk3".to_string(), false, None));
+        assert_eq!(
+            parse(r#"  firefox   explicit  "the \"good\" browser"  "#),
+            (
+                "firefox".to_string(),
+                true,
+                Some(r#"the "good" browser"#.to_string())
+            )
+        );
+        assert!(parse_decision_line("# a comment").unwrap().is_none());
+        assert!(parse_decision_line("   ").unwrap().is_none());
+    }
+
+    #[test]
+    fn rejects_malformed_decision_lines() {
+        assert!(parse_decision_line("firefox").is_err());
+        assert!(parse_decision_line("firefox maybe").is_err());
+        assert!(parse_decision_line("firefox explicit browser").is_err());
+        assert!(parse_decision_line(r#"firefox explicit "browser"#).is_err());
+        assert!(parse_decision_line(r#"firefox explicit "browser" again"#).is_err());
+    }
+}
@@ -1,95 +1,195 @@
-use crate::moree_state_manager::PackageState;
-use crate::package_manager_integration;
+use crate::utils;
+use colored::*;
+use moree::error::{self, Error};
+use moree::journal;
+use moree::moree_state_manager::PackageState;
+use moree::package_manager_integration::Backends;
+use moree::plan::{self, Outcome, Plan, StepResult};
+use moree::protected::Protected;
 use std::collections::HashMap;
 use std::io;
+use std::path::{Path, PathBuf};
 
-pub fn run(
-    package_states: &HashMap<String, PackageState>,
-    with_install: bool,
-    with_uninstall: bool,
-    sync: bool,
-) -> io::Result<()> {
-    if sync || (with_install && with_uninstall) {
-        apply_sync(package_states)
-    } else {
-        apply(package_states, with_install, with_uninstall)
-    }
+pub struct ApplyOptions {
+    pub with_install: bool,
+    pub with_uninstall: bool,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub plan_out: Option<PathBuf>,
+    pub plan_in: Option<PathBuf>,
+    pub resume: bool,
+    pub protected: Protected,
 }
 
-fn apply(
+pub fn run(
     package_states: &HashMap<String, PackageState>,
-    with_install: bool,
-    with_uninstall: bool,
-) -> io::Result<()> {
-    let installed_packages = package_manager_integration::get_installed_packages_hashset()?;
-    let mut to_install = Vec::new();
-    let mut to_install_asdeps = Vec::new();
+    backends: &Backends,
+    journal_dir: &Path,
+    options: &ApplyOptions,
+) -> error::Result<()> {
+    if options.resume {
+        return resume(backends, journal_dir, options);
+    }
 
-    for (package, state) in package_states {
-        if state.explicit {
-            if with_install && !installed_packages.contains(package) {
-                to_install.push(package.clone())
+    let plan = match &options.plan_in {
+        Some(plan_path) => {
+            let mut plan = plan::load_plan(plan_path)?;
+            let protected = plan.take_protected(backends, &options.protected)?;
+            if !protected.is_empty() {
+                return Err(io::Error::other(format!(
+                    "The plan removes protected packages: {}",
+                    protected.join(", ")
+                ))
+                .into());
             }
-        } else {
-            if installed_packages.contains(package) {
-                to_install_asdeps.push(package.clone())
+            plan.check(backends)?;
+            plan
+        }
+        None => {
+            let mut plan = Plan::build(
+                package_states,
+                backends,
+                options.with_install,
+                options.with_uninstall,
+            )?;
+            let protected = plan.take_protected(backends, &options.protected)?;
+            if !protected.is_empty() {
+                println!(
+                    "{} {}",
+                    "Not removing protected packages:".yellow(),
+                    protected.join(", ")
+                );
             }
+            plan
         }
-    }
+    };
 
-    if !to_install.is_empty() {
-        package_manager_integration::install_packages(&to_install)?;
+    // A written plan is meant to be applied later with --plan-in.
+    if let Some(plan_path) = &options.plan_out {
+        plan.print(backends);
+        plan::save_plan(plan, plan_path)?;
+        return Ok(());
     }
 
-    if !to_install_asdeps.is_empty() {
-        package_manager_integration::install_packages_as_deps(&to_install_asdeps)?;
-    }
-
-    let mut to_remove = Vec::new();
-    if with_uninstall {
-        for package in installed_packages {
-            if !package_states.contains_key(&package) {
-                to_remove.push(package)
-            }
-        }
+    // Install reasons are changed in the package database directly, without reinstalling.
+    run_plan(
+        &plan,
+        backends,
+        journal_dir,
+        "apply",
+        options.dry_run,
+        options.yes,
+    )
+}
 
-        if !to_remove.is_empty() {
-            package_manager_integration::remove_packages(&to_remove)?;
-        }
+// Retries the batches of the most recent run that failed or were skipped.
+fn resume(backends: &Backends, journal_dir: &Path, options: &ApplyOptions) -> error::Result<()> {
+    let entry = journal::load_entry(journal_dir, None)?;
+    let plan = Plan::unfinished_of(&entry.steps);
+    if plan.is_empty() {
+        println!(
+            "Nothing to resume: journal entry {} ({}) completed.",
+            entry.id, entry.command
+        );
+        return Ok(());
     }
 
-    Ok(())
+    println!("Resuming journal entry {} ({}).", entry.id, entry.command);
+    plan.check(backends)?;
+    run_plan(
+        &plan,
+        backends,
+        journal_dir,
+        &format!("resume {}", entry.id),
+        options.dry_run,
+        options.yes,
+    )
 }
 
-fn apply_sync(package_states: &HashMap<String, PackageState>) -> io::Result<()> {
-    let installed_packages = package_manager_integration::get_installed_packages_hashset()?;
-    let mut to_install = Vec::new();
-    let mut to_remove = Vec::new();
+// Prints the plan, asks for confirmation and carries it out, recording the outcome of
+// every batch in the journal.
+pub fn run_plan(
+    plan: &Plan,
+    backends: &Backends,
+    journal_dir: &Path,
+    command: &str,
+    dry_run: bool,
+    yes: bool,
+) -> error::Result<()> {
+    let results = execute_plan(plan, backends, journal_dir, command, dry_run, yes)?;
+    ensure_finished(&results)
+}
 
-    for (package, state) in package_states {
-        if state.explicit && !installed_packages.contains(package) {
-            to_install.push(package.clone());
-        }
+// Like `run_plan`, but returns the outcome of every batch instead of failing when some
+// did not complete. Nothing is returned for an empty plan or a dry run.
+pub fn execute_plan(
+    plan: &Plan,
+    backends: &Backends,
+    journal_dir: &Path,
+    command: &str,
+    dry_run: bool,
+    yes: bool,
+) -> error::Result<Vec<StepResult>> {
+    if plan.is_empty() {
+        println!("Nothing to do.");
+        return Ok(Vec::new());
     }
 
-    for package in &installed_packages {
-        if !package_states.contains_key(package) {
-            to_remove.push(package.clone());
-        } else if let Some(state) = package_states.get(package) {
-            if !state.explicit {
-                // Install as dependency if it exists and marked as not explicit.
-                package_manager_integration::install_packages_as_deps(&[package.clone()])?;
-            }
-        }
+    plan.print(backends);
+    if dry_run {
+        return Ok(Vec::new());
     }
 
-    if !to_install.is_empty() {
-        package_manager_integration::install_packages(&to_install)?;
+    if !yes {
+        utils::require_terminal("pass --yes to carry out the plan without confirmation")?;
+        if !utils::confirm_prompt("Apply these changes?", true)? {
+            return Err(Error::UserAborted);
+        }
     }
 
-    if !to_remove.is_empty() {
-        package_manager_integration::remove_packages(&to_remove)?;
-    }
+    let results = plan.execute(backends);
+    print_summary(&results, backends);
+    let id = journal::record(journal_dir, command, results.clone())?;
+    println!("Recorded as journal entry {}.", id);
+
+    Ok(results)
+}
 
+pub fn ensure_finished(results: &[StepResult]) -> error::Result<()> {
+    let unfinished = results
+        .iter()
+        .filter(|result| result.outcome != Outcome::Succeeded)
+        .count();
+    if unfinished > 0 {
+        return Err(Error::PartiallyApplied {
+            unfinished,
+            total: results.len(),
+        });
+    }
     Ok(())
 }
+
+fn print_summary(results: &[StepResult], backends: &Backends) {
+    println!("{}", "Summary:".bold());
+    for result in results {
+        let packages = result
+            .step
+            .packages
+            .iter()
+            .map(|package| backends.label(&result.step.namespace, package))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let batch = format!("{}: {}", result.step.action.description(), packages);
+        match &result.outcome {
+            Outcome::Succeeded => println!("  {}  {}", "succeeded".green(), batch),
+            Outcome::Failed { error } => {
+                println!("  {}     {} ({})", "failed".red(), batch, error)
+            }
+            Outcome::Skipped => println!(
+                "  {}    {} (an earlier batch for this backend failed)",
+                "skipped".yellow(),
+                batch
+            ),
+        }
+    }
+}
@@ -1,40 +1,41 @@
-use crate::moree_state_manager::{self, PackageState};
+use crate::commands::add;
 use crate::utils;
+use chrono::Utc;
 use colored::*;
-use dialoguer::{theme::ColorfulTheme, Input, Select};
+use moree::error::{self, Error};
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
 use std::collections::HashMap;
-use std::io;
-use std::path::PathBuf;
+use std::path::Path;
 
 pub fn run(
     package_states: &mut HashMap<String, PackageState>,
-    state_file_path: &PathBuf,
-    package_name: &str,
-) -> io::Result<()> {
-    if !package_states.contains_key(package_name) {
-        println!(
-            "{} {} {}",
-            "Package".bold().yellow(),
-            package_name.bold().yellow(),
-            "is not managed.  Use `add` to manage this package."
-                .bold()
-                .yellow(),
-        );
-        return Ok(()); // Or Err if you want to treat this as an error condition
-    }
+    state_file_path: &Path,
+    backends: &Backends,
+    package: &str,
+) -> error::Result<()> {
+    let Some(package_name) =
+        moree_state_manager::find_package_key(package_states, &backends.namespaces(), package)?
+    else {
+        return Err(Error::NotManaged(package.to_string()));
+    };
+    let package_name = package_name.as_str();
+    utils::require_terminal(
+        "edit prompts for every change; use `add <package> --explicit|--dep --memo <text>` instead",
+    )?;
 
-    let options = &["Explicit Status", "Memo", "Quit"];
+    let original = package_states[package_name].clone();
+    let options = &["Explicit Status", "Memo", "Tags", "Quit"];
     loop {
-        let selection = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!(
+        let selection = utils::select_prompt(
+            &format!(
                 "Edit {} (current explicit status: {})",
-                package_name.bold(),
+                backends.label_key(package_name).bold(),
                 package_states[package_name].explicit
-            ))
-            .items(options)
-            .default(0)
-            .interact()
-            .unwrap();
+            ),
+            options,
+            0,
+        )?;
 
         match options[selection] {
             "Explicit Status" => {
@@ -42,6 +43,13 @@ pub fn run(
                     "Is this package explicitly installed?",
                     package_states[package_name].explicit,
                 )?;
+                let (namespace, name) = moree_state_manager::split_qualified_name(package_name);
+                if !new_status {
+                    if let Some(reason) = add::refuse_dependency(backends, namespace, name) {
+                        println!("{}", reason.yellow());
+                        continue;
+                    }
+                }
                 package_states
                     .entry(package_name.to_string())
                     .and_modify(|state| {
@@ -53,12 +61,10 @@ pub fn run(
                     .memo
                     .clone()
                     .unwrap_or_default();
-                let memo: String = Input::new()
-                    .with_prompt("Enter new memo (optional, enter to keep current memo):")
-                    .with_initial_text(current_memo)
-                    .allow_empty(true)
-                    .interact_text()
-                    .unwrap();
+                let memo = utils::input_prompt(
+                    "Enter new memo (optional, enter to keep current memo):",
+                    &current_memo,
+                )?;
 
                 package_states
                     .entry(package_name.to_string())
@@ -66,11 +72,30 @@ pub fn run(
                         state.memo = Some(memo).filter(|s| !s.is_empty());
                     });
             }
+            "Tags" => {
+                let current_tags = package_states[package_name].tags.join(", ");
+                let tags = utils::input_prompt(
+                    "Enter tags separated by commas (enter to keep current tags):",
+                    &current_tags,
+                )?;
+
+                package_states
+                    .entry(package_name.to_string())
+                    .and_modify(|state| {
+                        state.tags = moree_state_manager::parse_tags(&tags);
+                    });
+            }
             "Quit" => break,
             _ => unreachable!(),
         }
     }
 
+    if let Some(state) = package_states
+        .get_mut(package_name)
+        .filter(|state| !state.same_reason(&original))
+    {
+        state.stamp(Some(&original), Utc::now());
+    }
     moree_state_manager::save_package_states(state_file_path, package_states)?;
 
     Ok(())
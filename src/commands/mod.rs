@@ -2,5 +2,10 @@ pub mod add;
 pub mod apply;
 pub mod diff;
 pub mod edit;
+pub mod orphans;
 pub mod query;
+pub mod review;
+pub mod rollback;
+pub mod tags;
 pub mod unmanaged;
+pub mod why;
@@ -0,0 +1,149 @@
+use super::apply;
+use colored::*;
+use moree::error;
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
+use moree::plan::{Action, Outcome, Plan, Step};
+use moree::protected::Protected;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub struct OrphansOptions {
+    pub remove: bool,
+    pub dry_run: bool,
+    pub yes: bool,
+    pub protected: Protected,
+}
+
+// Lists dependencies that nothing requires any more, and state entries recorded as
+// dependencies that nothing requires. With `remove`, the orphans are removed through
+// the backend and their entries forgotten, so `apply --with-install` does not bring
+// them back.
+pub fn run(
+    package_states: &mut HashMap<String, PackageState>,
+    state_file_path: &Path,
+    backends: &Backends,
+    journal_dir: &Path,
+    options: &OrphansOptions,
+) -> error::Result<()> {
+    let mut orphans = Vec::new();
+    let mut stale_entries = Vec::new();
+    let mut plan = Plan::default();
+
+    for backend in backends.iter() {
+        let namespace = backend.namespace();
+        let mut packages = backend.list_orphans()?;
+        packages.sort();
+        let installed = backend.get_installed_packages_hashset()?;
+        let installed_asdeps = backend.get_installed_packages_asdeps_hashset()?;
+
+        for package in &packages {
+            let label = backends.label(namespace, package);
+            let key = moree_state_manager::qualified_name(namespace, package);
+            match package_states
+                .get(&key)
+                .and_then(|state| state.memo.as_deref())
+            {
+                Some(memo) => orphans.push(format!("{}: {}", label, memo)),
+                None => orphans.push(label),
+            }
+        }
+
+        let mut entries: Vec<_> = moree_state_manager::package_states_in(package_states, namespace)
+            .into_iter()
+            .filter(|(_, state)| !state.explicit)
+            .filter_map(|(package, _)| {
+                if packages.contains(&package) {
+                    Some((package, "orphaned"))
+                } else if !installed.contains(&package) && !installed_asdeps.contains(&package) {
+                    Some((package, "not installed"))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        entries.sort();
+        stale_entries.extend(entries.into_iter().map(|(package, reason)| {
+            format!("{} ({})", backends.label(namespace, &package), reason)
+        }));
+
+        if !packages.is_empty() {
+            plan.steps.push(Step {
+                namespace: namespace.to_string(),
+                action: Action::RemoveDep,
+                packages,
+            });
+        }
+    }
+
+    if orphans.is_empty() {
+        println!("No orphaned dependencies.");
+    } else {
+        println!(
+            "{}",
+            format!("Orphaned dependencies ({}):", orphans.len()).bold()
+        );
+        for orphan in &orphans {
+            println!("  {}", orphan);
+        }
+    }
+    if !stale_entries.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "Recorded as dependencies, but nothing requires them ({}):",
+                stale_entries.len()
+            )
+            .bold()
+            .yellow()
+        );
+        for entry in &stale_entries {
+            println!("  {}", entry);
+        }
+    }
+
+    if !options.remove {
+        return Ok(());
+    }
+
+    let protected = plan.take_protected(backends, &options.protected)?;
+    if !protected.is_empty() {
+        println!(
+            "{} {}",
+            "Not removing protected packages:".yellow(),
+            protected.join(", ")
+        );
+    }
+    let results = apply::execute_plan(
+        &plan,
+        backends,
+        journal_dir,
+        "orphans",
+        options.dry_run,
+        options.yes,
+    )?;
+
+    let removed: Vec<String> =
+        results
+            .iter()
+            .filter(|result| result.outcome == Outcome::Succeeded)
+            .flat_map(|result| {
+                result.step.packages.iter().map(|package| {
+                    moree_state_manager::qualified_name(&result.step.namespace, package)
+                })
+            })
+            .filter(|key| package_states.contains_key(key))
+            .collect();
+    if !removed.is_empty() {
+        for key in &removed {
+            package_states.remove(key);
+        }
+        moree_state_manager::save_package_states(state_file_path, package_states)?;
+        println!(
+            "Forgot {} state entries of removed packages.",
+            removed.len()
+        );
+    }
+
+    apply::ensure_finished(&results)
+}
@@ -1,23 +1,32 @@
-use crate::moree_state_manager::PackageState;
+use chrono::{DateTime, Local, NaiveDate, Utc};
 use colored::*;
+use moree::error;
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
 use std::collections::HashMap;
 use std::io;
 
 pub fn run(
     package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
     information: bool,
     explicit: bool,
     deps: bool,
-) -> io::Result<()> {
+    tag: Option<&str>,
+    reviewed_before: Option<NaiveDate>,
+) -> error::Result<()> {
     if explicit && deps {
         return Err(io::Error::new(
             io::ErrorKind::InvalidInput,
             "Error: '--deps' and '--explicit' may not be used together",
-        ));
+        )
+        .into());
     }
 
+    let namespaces = backends.namespaces();
     let mut packages: Vec<_> = package_states
         .iter()
+        .filter(|(key, _)| namespaces.contains(&moree_state_manager::split_qualified_name(key).0))
         .filter(|(_, state)| {
             if explicit {
                 state.explicit
@@ -27,19 +36,30 @@ pub fn run(
                 true // No filter if neither --explicit nor --deps is specified
             }
         })
-        .map(|(name, _)| name)
+        .filter(|(_, state)| tag.is_none_or(|tag| state.tags.iter().any(|t| t == tag)))
+        .filter(|(_, state)| {
+            // Entries that were never reviewed count as reviewed before any date.
+            reviewed_before.is_none_or(|date| {
+                state
+                    .reviewed_at
+                    .is_none_or(|reviewed_at| reviewed_at.with_timezone(&Local).date_naive() < date)
+            })
+        })
+        .map(|(key, _)| key)
         .cloned()
         .collect();
 
-    packages.sort();
+    packages.sort_by_key(|key| backends.label_key(key));
 
     if information {
-        for package_name in &packages {
+        for key in &packages {
             println!(
                 "{}",
-                format!("Name            : {}", package_name).bold().blue()
+                format!("Name            : {}", backends.label_key(key))
+                    .bold()
+                    .blue()
             );
-            if let Some(state) = package_states.get(package_name) {
+            if let Some(state) = package_states.get(key) {
                 println!(
                     "{}",
                     format!(
@@ -62,15 +82,43 @@ pub fn run(
                     .bold()
                     .green()
                 );
+                println!(
+                    "{}",
+                    format!(
+                        "Tags            : {}",
+                        if state.tags.is_empty() {
+                            "None".to_string()
+                        } else {
+                            state.tags.join(", ")
+                        }
+                    )
+                    .bold()
+                    .magenta()
+                );
+                println!("Added           : {}", format_time(state.added_at));
+                println!("Modified        : {}", format_time(state.modified_at));
+                println!("Reviewed        : {}", format_time(state.reviewed_at));
             } else {
-                eprintln!("Package {} not found in state", package_name); // Handle unexpected case
+                eprintln!("Package {} not found in state", key); // Handle unexpected case
             }
             println!(); // Add a newline for separation
         }
     } else {
-        let output = packages.join("\n");
+        let output = packages
+            .iter()
+            .map(|key| backends.label_key(key))
+            .collect::<Vec<_>>()
+            .join("\n");
         println!("{}", output);
     }
 
     Ok(())
 }
+
+fn format_time(time: Option<DateTime<Utc>>) -> String {
+    time.map_or("Unknown".to_string(), |time| {
+        time.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    })
+}
@@ -0,0 +1,212 @@
+use crate::commands::add;
+use crate::utils;
+use chrono::{Duration, Utc};
+use colored::*;
+use moree::error::{self, Error};
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+pub const DEFAULT_REVIEW_INTERVAL_DAYS: u32 = 180;
+
+// Goes through the explicit packages whose reason was not confirmed within the last
+// `interval_days`, least recently reviewed first.
+pub fn run(
+    package_states: &mut HashMap<String, PackageState>,
+    state_file_path: &Path,
+    backends: &Backends,
+    interval_days: u32,
+) -> error::Result<()> {
+    let due = get_packages_due(package_states, backends, interval_days);
+    if due.is_empty() {
+        println!(
+            "No reasons are older than {} days; nothing to review.",
+            interval_days
+        );
+        return Ok(());
+    }
+    utils::require_terminal(
+        "review prompts for every package; use `add <package> --explicit` to confirm reasons without prompts",
+    )?;
+    println!("{} packages are due for review.", due.len());
+
+    let mut installed = HashMap::new();
+    for backend in backends.iter() {
+        let mut packages: HashSet<String> = backend.get_installed_packages_hashset()?;
+        packages.extend(backend.get_installed_packages_asdeps_hashset()?);
+        installed.insert(backend.namespace(), packages);
+    }
+
+    let mut should_save = true;
+    let mut marked_for_removal = Vec::new();
+    for key in due {
+        let (namespace, package_name) = moree_state_manager::split_qualified_name(&key);
+        add::print_package_header(backends, namespace, package_name);
+        match backends.get(namespace) {
+            Some(backend) if installed[namespace].contains(package_name) => {
+                backend.display_package_details(package_name)?
+            }
+            _ => println!("{}", "Not installed".yellow()),
+        }
+
+        let can_demote = backends
+            .get(namespace)
+            .is_some_and(|backend| backend.supports_install_reasons());
+        match review_package(package_states, &key, can_demote) {
+            Ok(true) => marked_for_removal.push(backends.label_key(&key)),
+            Ok(false) => {}
+            Err(Error::UserAborted) => {
+                should_save = utils::confirm_prompt("Save changes before quitting? (Y/n)", true)?;
+                break;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !should_save {
+        return Ok(());
+    }
+    moree_state_manager::save_package_states(state_file_path, package_states)?;
+    if !marked_for_removal.is_empty() {
+        println!(
+            "No longer managed: {}. Run `moree apply --with-uninstall` to remove them.",
+            marked_for_removal.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+// Explicit packages of the selected backends that were never reviewed or not within
+// the interval. Entries that were never reviewed come first.
+fn get_packages_due(
+    package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
+    interval_days: u32,
+) -> Vec<String> {
+    let namespaces = backends.namespaces();
+    let cutoff = Utc::now() - Duration::days(interval_days.into());
+    let mut due: Vec<_> = package_states
+        .iter()
+        .filter(|(key, state)| {
+            state.explicit
+                && namespaces.contains(&moree_state_manager::split_qualified_name(key).0)
+                && state
+                    .reviewed_at
+                    .is_none_or(|reviewed_at| reviewed_at < cutoff)
+        })
+        .map(|(key, state)| (state.reviewed_at, key.clone()))
+        .collect();
+    due.sort();
+
+    due.into_iter().map(|(_, key)| key).collect()
+}
+
+// Asks what to do with one package and records the answer. Demoting is only offered
+// when the backend has install reasons. Returns whether the package was marked for
+// removal.
+fn review_package(
+    package_states: &mut HashMap<String, PackageState>,
+    key: &str,
+    can_demote: bool,
+) -> error::Result<bool> {
+    let state = package_states[key].clone();
+    println!(
+        "Memo: {}",
+        state.memo.as_deref().unwrap_or("None").bold().green()
+    );
+
+    let options: Vec<&str> = [
+        "Keep",
+        "Update memo",
+        "Demote to dependency",
+        "Mark for removal",
+        "Skip",
+        "Quit",
+    ]
+    .into_iter()
+    .filter(|option| can_demote || *option != "Demote to dependency")
+    .collect();
+    let selection = utils::select_prompt("Do you still need this package?", &options, 0)?;
+
+    match options[selection] {
+        "Keep" => add::insert_stamped(package_states, key.to_string(), state),
+        "Update memo" => {
+            let memo = utils::input_prompt(
+                "Why do you need this package? (enter to keep current memo)",
+                state.memo.as_deref().unwrap_or_default(),
+            )?;
+            add::insert_stamped(
+                package_states,
+                key.to_string(),
+                PackageState {
+                    memo: Some(memo).filter(|s| !s.is_empty()),
+                    ..state
+                },
+            );
+        }
+        "Demote to dependency" => add::insert_stamped(
+            package_states,
+            key.to_string(),
+            PackageState {
+                explicit: false,
+                ..state
+            },
+        ),
+        "Mark for removal" => {
+            package_states.remove(key);
+            return Ok(true);
+        }
+        "Skip" => {}
+        "Quit" => return Err(Error::UserAborted),
+        _ => unreachable!(),
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use moree::package_manager_integration::{self, BackendOptions};
+
+    fn state(explicit: bool, reviewed_at: Option<&str>) -> PackageState {
+        PackageState {
+            explicit,
+            reviewed_at: reviewed_at.map(|time| time.parse().unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn lists_due_packages_least_recently_reviewed_first() {
+        let options = BackendOptions {
+            db_path: Some("system.json".into()),
+            ..Default::default()
+        };
+        std::env::set_var(package_manager_integration::FAKE_BACKEND_VAR, "1");
+        let backends =
+            package_manager_integration::get_backends(&["fake".to_string()], &options).unwrap();
+        let recent = (Utc::now() - Duration::days(1)).to_rfc3339();
+        let package_states = HashMap::from([
+            (
+                "fake:neovim".to_string(),
+                state(true, Some("2024-06-15T10:00:00Z")),
+            ),
+            (
+                "fake:firefox".to_string(),
+                state(true, Some("2023-01-01T10:00:00Z")),
+            ),
+            ("fake:git".to_string(), state(true, None)),
+            ("fake:curl".to_string(), state(true, Some(&recent))),
+            ("fake:gtk3".to_string(), state(false, None)),
+            ("cargo:ripgrep".to_string(), state(true, None)),
+        ]);
+
+        assert_eq!(
+            get_packages_due(&package_states, &backends, 30),
+            ["fake:git", "fake:firefox", "fake:neovim"]
+        );
+    }
+}
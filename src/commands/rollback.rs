@@ -0,0 +1,43 @@
+use super::apply;
+use moree::error;
+use moree::journal;
+use moree::package_manager_integration::Backends;
+use moree::plan::Plan;
+use moree::protected::Protected;
+use std::io;
+use std::path::Path;
+
+pub fn run(
+    backends: &Backends,
+    journal_dir: &Path,
+    id: Option<u64>,
+    dry_run: bool,
+    yes: bool,
+    protected: &Protected,
+) -> error::Result<()> {
+    let entry = journal::load_entry(journal_dir, id)?;
+    println!(
+        "Rolling back journal entry {} ({}, {}).",
+        entry.id, entry.command, entry.timestamp
+    );
+
+    // Undoing an install removes the package, which must not take a protected one.
+    let mut plan = Plan::inverse_of(&entry.steps);
+    let protected = plan.take_protected(backends, protected)?;
+    if !protected.is_empty() {
+        return Err(io::Error::other(format!(
+            "The rollback removes protected packages: {}",
+            protected.join(", ")
+        ))
+        .into());
+    }
+    plan.check(backends)?;
+    apply::run_plan(
+        &plan,
+        backends,
+        journal_dir,
+        &format!("rollback {}", entry.id),
+        dry_run,
+        yes,
+    )
+}
@@ -0,0 +1,28 @@
+use moree::error;
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
+use std::collections::{BTreeMap, HashMap};
+
+// Lists every tag in use with the number of packages that carry it.
+pub fn run(
+    package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
+) -> error::Result<()> {
+    let namespaces = backends.namespaces();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for (key, state) in package_states {
+        if !namespaces.contains(&moree_state_manager::split_qualified_name(key).0) {
+            continue;
+        }
+        for tag in &state.tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    let width = counts.keys().map(|tag| tag.len()).max().unwrap_or(0);
+    for (tag, count) in counts {
+        println!("{:<width$}  {}", tag, count, width = width);
+    }
+
+    Ok(())
+}
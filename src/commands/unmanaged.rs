@@ -1,15 +1,26 @@
-use crate::moree_state_manager::PackageState;
-use crate::package_manager_integration;
+use moree::error;
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
 use std::collections::HashMap;
-use std::io;
 
-pub fn run(package_states: &HashMap<String, PackageState>) -> io::Result<()> {
-    let installed_packages = package_manager_integration::get_installed_packages_hashset()?;
-    let mut unmanaged_packages: Vec<_> = installed_packages
-        .iter()
-        .filter(|package| !package_states.contains_key(*package))
-        .cloned()
-        .collect();
+pub fn run(
+    package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
+) -> error::Result<()> {
+    let mut unmanaged_packages = Vec::new();
+    for backend in backends.iter() {
+        let namespace = backend.namespace();
+        let installed_packages = backend.get_installed_packages_hashset()?;
+        unmanaged_packages.extend(
+            installed_packages
+                .iter()
+                .filter(|package| {
+                    !package_states
+                        .contains_key(&moree_state_manager::qualified_name(namespace, package))
+                })
+                .map(|package| backends.label(namespace, package)),
+        );
+    }
 
     unmanaged_packages.sort();
 
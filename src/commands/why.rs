@@ -0,0 +1,169 @@
+use colored::*;
+use moree::error::{self, Error};
+use moree::moree_state_manager::{self, PackageState};
+use moree::package_manager_integration::Backends;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// Explains why a package is installed: its own memo, and for a dependency the chains
+// of packages that lead to it from the explicitly installed ones, with their memos.
+pub fn run(
+    package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
+    package: &str,
+) -> error::Result<()> {
+    let index = backends.installed_index()?;
+    let Some((backend, package_name)) = index.locate(package)? else {
+        // A package that is recorded but not installed can still be explained by its memo.
+        let Some(key) =
+            moree_state_manager::find_package_key(package_states, &backends.namespaces(), package)?
+        else {
+            return Err(Error::PackageNotInstalled(vec![package.to_string()]));
+        };
+        println!("{} is not installed.", backends.label_key(&key).bold());
+        print_memo(package_states[&key].memo.as_deref());
+        return Ok(());
+    };
+
+    let namespace = backend.namespace();
+    let memo_of = |name: &str| {
+        package_states
+            .get(&moree_state_manager::qualified_name(namespace, name))
+            .and_then(|state| state.memo.as_deref())
+    };
+    let explicit = backend.get_installed_packages_hashset()?;
+
+    if explicit.contains(&package_name) {
+        println!(
+            "{} is explicitly installed.",
+            backends.label(namespace, &package_name).bold()
+        );
+        print_memo(memo_of(&package_name));
+        return Ok(());
+    }
+
+    println!(
+        "{} is installed as a dependency.",
+        backends.label(namespace, &package_name).bold()
+    );
+    if let Some(memo) = memo_of(&package_name) {
+        print_memo(Some(memo));
+    }
+
+    let chains = dependency_chains(&backend.required_by()?, &explicit, &package_name);
+    if chains.is_empty() {
+        println!("Nothing explicitly installed requires it.");
+        return Ok(());
+    }
+
+    println!("Required by:");
+    for chain in chains {
+        let root = &chain[0];
+        println!(
+            "  {}",
+            chain
+                .iter()
+                .map(|package| backends.label(namespace, package))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+        println!(
+            "    {}: {}",
+            backends.label(namespace, root).green(),
+            memo_of(root).unwrap_or("no memo recorded")
+        );
+    }
+
+    Ok(())
+}
+
+fn print_memo(memo: Option<&str>) {
+    match memo {
+        Some(memo) => println!("Memo: {}", memo.green()),
+        None => println!("No memo recorded; add one with `moree edit`."),
+    }
+}
+
+// Walks up from `package` through the packages that require it and returns, for every
+// explicitly installed package it reaches, the shortest chain from that package down
+// to `package`. Explicit packages end the walk; chains are sorted by where they start.
+fn dependency_chains(
+    required_by: &HashMap<String, Vec<String>>,
+    explicit: &HashSet<String>,
+    package: &str,
+) -> Vec<Vec<String>> {
+    // Each visited package, mapped to the dependency it was reached from.
+    let mut reached_from: HashMap<&str, &str> = HashMap::new();
+    let mut visited = HashSet::from([package]);
+    let mut queue = VecDeque::from([package]);
+    let mut roots = Vec::new();
+
+    while let Some(current) = queue.pop_front() {
+        let mut dependents: Vec<&str> = required_by
+            .get(current)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        dependents.sort();
+
+        for dependent in dependents {
+            if !visited.insert(dependent) {
+                continue;
+            }
+            reached_from.insert(dependent, current);
+            if explicit.contains(dependent) {
+                roots.push(dependent);
+            } else {
+                queue.push_back(dependent);
+            }
+        }
+    }
+
+    roots.sort();
+    roots
+        .into_iter()
+        .map(|root| {
+            let mut chain = vec![root.to_string()];
+            let mut current = root;
+            while let Some(next) = reached_from.get(current) {
+                chain.push(next.to_string());
+                current = next;
+            }
+            chain
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        edges
+            .iter()
+            .map(|(package, dependents)| {
+                (
+                    package.to_string(),
+                    dependents.iter().map(|d| d.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_the_shortest_chain_from_each_explicit_package() {
+        let required_by = graph(&[
+            ("glibc", &["gtk3", "zsh", "firefox"]),
+            ("gtk3", &["firefox", "gimp"]),
+            ("zsh", &["glibc"]),
+            ("gimp", &[]),
+        ]);
+        let explicit = HashSet::from(["firefox".to_string(), "gimp".to_string()]);
+
+        assert_eq!(
+            dependency_chains(&required_by, &explicit, "glibc"),
+            [vec!["firefox", "glibc"], vec!["gimp", "gtk3", "glibc"]]
+        );
+        assert!(dependency_chains(&required_by, &explicit, "gimp").is_empty());
+    }
+}
@@ -0,0 +1,62 @@
+//! The optional user configuration in `config.json` under moree's config directory.
+
+use crate::error::Result;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const PROGRAM_NAME: &str = "moree";
+const DEFAULT_CONFIG_FILE: &str = "config.json";
+
+/// Settings read from the config file. Every field is optional.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    /// The backend to use when `--backend` is not given. Superseded by `backends`.
+    pub backend: Option<String>,
+    /// The backends to use together when `--backend` is not given.
+    pub backends: Vec<String>,
+    /// Extra patterns that are never removed, on top of the built-in ones.
+    pub protected: Vec<String>,
+    /// How many days a confirmed reason stays fresh before `review` asks about it again.
+    pub review_interval_days: Option<u32>,
+}
+
+impl Config {
+    /// The configured backends, from `backends` or else `backend`. Empty when neither
+    /// is set, which selects the default backend.
+    pub fn backend_names(&self) -> Vec<String> {
+        if !self.backends.is_empty() {
+            self.backends.clone()
+        } else {
+            self.backend.iter().cloned().collect()
+        }
+    }
+}
+
+/// Where the config file is looked up, if the platform has a config directory.
+pub fn get_config_file_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(PROGRAM_NAME).join(DEFAULT_CONFIG_FILE))
+}
+
+/// Reads the config file. A missing file gives the default configuration.
+pub fn load_config() -> Result<Config> {
+    let Some(file_path) = get_config_file_path() else {
+        return Ok(Config::default());
+    };
+
+    let data = match fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+        Err(err) => return Err(err.into()),
+    };
+
+    serde_json::from_str(&data).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse config file {}: {}", file_path.display(), e),
+        )
+        .into()
+    })
+}
@@ -0,0 +1,87 @@
+//! Differences between the recorded state and what is installed.
+
+use crate::error::Result;
+use crate::moree_state_manager::{self, PackageState};
+use crate::package_manager_integration::Backends;
+use std::collections::HashMap;
+
+/// How a package on the system differs from its recorded state, in the order
+/// differences are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DifferenceKind {
+    /// Recorded as explicitly installed, but installed as a dependency.
+    ExpectedExplicit,
+    /// Recorded as a dependency, but installed explicitly.
+    ExpectedDependency,
+    /// Recorded as explicitly installed, but not installed at all.
+    NotInstalled,
+    /// Installed explicitly without being recorded. Unrecorded dependencies are not
+    /// reported.
+    Unmanaged,
+}
+
+/// A package whose recorded state does not match the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Difference {
+    /// The namespace of the backend the package belongs to.
+    pub namespace: String,
+    /// The bare package name.
+    pub package: String,
+    /// How the package differs.
+    pub kind: DifferenceKind,
+}
+
+/// Compares `package_states` with the packages installed by each backend. Install
+/// reason mismatches are always reported; with `all`, missing and unmanaged packages
+/// are reported as well. Differences are grouped by backend, then by kind.
+pub fn diff(
+    package_states: &HashMap<String, PackageState>,
+    backends: &Backends,
+    all: bool,
+) -> Result<Vec<Difference>> {
+    let mut differences = Vec::new();
+    for backend in backends.iter() {
+        let namespace = backend.namespace();
+        let package_states = moree_state_manager::package_states_in(package_states, namespace);
+        let installed_explicit = backend.get_installed_packages_hashset()?;
+        let installed_asdeps = backend.get_installed_packages_asdeps_hashset()?;
+
+        let mut found = Vec::new();
+        for (package, state) in &package_states {
+            // Without install reasons, every package is installed explicitly.
+            let explicit = state.explicit || !backend.supports_install_reasons();
+            let kind = if explicit && installed_asdeps.contains(package) {
+                Some(DifferenceKind::ExpectedExplicit)
+            } else if !explicit && installed_explicit.contains(package) {
+                Some(DifferenceKind::ExpectedDependency)
+            } else if all
+                && explicit
+                && !installed_explicit.contains(package)
+                && !installed_asdeps.contains(package)
+            {
+                Some(DifferenceKind::NotInstalled)
+            } else {
+                None
+            };
+            found.extend(kind.map(|kind| (kind, package.clone())));
+        }
+
+        if all {
+            found.extend(
+                installed_explicit
+                    .iter()
+                    .filter(|package| !package_states.contains_key(*package))
+                    .map(|package| (DifferenceKind::Unmanaged, package.clone())),
+            );
+        }
+
+        found.sort();
+        differences.extend(found.into_iter().map(|(kind, package)| Difference {
+            namespace: namespace.to_string(),
+            package,
+            kind,
+        }));
+    }
+
+    Ok(differences)
+}
@@ -0,0 +1,168 @@
+//! The error type shared by the library and the command.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus};
+
+/// A result with moree's [`Error`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything moree can fail with. Each variant has its own exit code; see
+/// `exit_code` and the README. Code 2 is left to clap for usage errors.
+#[derive(Debug)]
+pub enum Error {
+    /// The user quit or declined a prompt.
+    UserAborted,
+    /// A package manager command exited unsuccessfully. `stderr` is empty when the
+    /// command wrote straight to the terminal.
+    BackendFailed {
+        /// The command line that failed.
+        command: String,
+        /// The exit code, or `None` when the command was killed by a signal.
+        exit_code: Option<i32>,
+        /// What the command wrote to stderr, trimmed.
+        stderr: String,
+    },
+    /// The state file is not valid JSON or does not match the schema. Errors found
+    /// after parsing, such as a missing field, have no position and report line 0.
+    StateParse {
+        /// The state file.
+        path: PathBuf,
+        /// The line of the error, starting at 1, or 0 when there is no position.
+        line: usize,
+        /// The column of the error on that line.
+        column: usize,
+        /// What is wrong, without the position.
+        message: String,
+    },
+    /// None of the selected backends has these packages installed.
+    PackageNotInstalled(Vec<String>),
+    /// The package has no entry in the state file.
+    NotManaged(String),
+    /// Some batches of an apply run failed or were skipped; the rest went through.
+    PartiallyApplied {
+        /// How many batches failed or were skipped.
+        unfinished: usize,
+        /// How many batches the run had.
+        total: usize,
+    },
+    /// Any other I/O error, including those of package managers that could not be run.
+    Io(io::Error),
+}
+
+impl Error {
+    /// The process exit code moree exits with for this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(_) => 1,
+            Error::UserAborted => 3,
+            Error::BackendFailed { .. } => 4,
+            Error::StateParse { .. } => 5,
+            Error::PackageNotInstalled(_) => 6,
+            Error::NotManaged(_) => 7,
+            Error::PartiallyApplied { .. } => 8,
+        }
+    }
+
+    /// A `BackendFailed` error for `command`, which exited with `status`.
+    pub fn backend_failed(command: &Command, status: ExitStatus, stderr: &[u8]) -> Self {
+        let command_line = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Error::BackendFailed {
+            command: command_line,
+            exit_code: status.code(),
+            stderr: String::from_utf8_lossy(stderr).trim().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UserAborted => write!(f, "Operation cancelled."),
+            Error::BackendFailed {
+                command,
+                exit_code,
+                stderr,
+            } => {
+                match exit_code {
+                    Some(code) => write!(f, "{} failed with exit code {}", command, code)?,
+                    None => write!(f, "{} was terminated by a signal", command)?,
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+            Error::StateParse {
+                path,
+                line,
+                column,
+                message,
+            } => {
+                write!(f, "Could not parse state file {}", path.display())?;
+                if *line > 0 {
+                    write!(f, " at line {}, column {}", line, column)?;
+                }
+                write!(f, ": {}", message)
+            }
+            Error::PackageNotInstalled(packages) => write!(
+                f,
+                "The following packages are not installed: {}",
+                packages.join(", ")
+            ),
+            Error::NotManaged(package) => write!(
+                f,
+                "Package {} is not managed. Use `add` to manage this package.",
+                package
+            ),
+            Error::PartiallyApplied { unfinished, total } => write!(
+                f,
+                "{} of {} batches did not complete; run `moree apply --resume` to retry them",
+                unfinished, total
+            ),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_failures_name_the_command_and_its_stderr() {
+        let error = Error::BackendFailed {
+            command: "pacman -S neovim".to_string(),
+            exit_code: Some(1),
+            stderr: "target not found: neovim".to_string(),
+        };
+
+        assert_eq!(error.exit_code(), 4);
+        assert_eq!(
+            error.to_string(),
+            "pacman -S neovim failed with exit code 1: target not found: neovim"
+        );
+        assert_eq!(Error::from(io::Error::other("plain")).exit_code(), 1);
+    }
+}
@@ -0,0 +1,123 @@
+//! The journal of `apply` and `rollback` runs, which `rollback` undoes.
+
+use crate::error::Result;
+use crate::plan::StepResult;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const JOURNAL_DIR: &str = "journal";
+
+/// A record of one `apply` or `rollback` run: the batches it executed and how each
+/// of them went. Entries are numbered in the order they were written.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JournalEntry {
+    /// The entry's number, starting at 1.
+    pub id: u64,
+    /// When the run finished, as an RFC 3339 local time.
+    pub timestamp: String,
+    /// The command that ran, such as `apply` or `rollback 3`.
+    pub command: String,
+    /// Every batch of the run, in the order it ran.
+    pub steps: Vec<StepResult>,
+}
+
+/// The journal lives next to the state file, in moree's data directory.
+pub fn get_journal_dir(state_file_path: &Path) -> PathBuf {
+    state_file_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(JOURNAL_DIR)
+}
+
+/// Writes a new entry for `steps` and returns its id.
+pub fn record(journal_dir: &Path, command: &str, steps: Vec<StepResult>) -> Result<u64> {
+    fs::create_dir_all(journal_dir)?;
+    let mut entry = JournalEntry {
+        id: latest_id(journal_dir)?.unwrap_or(0) + 1,
+        timestamp: chrono::Local::now().to_rfc3339(),
+        command: command.to_string(),
+        steps,
+    };
+
+    // Another moree may have taken the id in the meantime; never overwrite its entry.
+    loop {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(entry_path(journal_dir, entry.id))
+        {
+            Ok(mut file) => {
+                file.write_all(
+                    serde_json::to_string_pretty(&entry)
+                        .map_err(io::Error::from)?
+                        .as_bytes(),
+                )?;
+                file.sync_all()?;
+                return Ok(entry.id);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => entry.id += 1,
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Loads the given entry, or the most recent one.
+pub fn load_entry(journal_dir: &Path, id: Option<u64>) -> Result<JournalEntry> {
+    let Some(id) = id.map_or_else(|| latest_id(journal_dir), |id| Ok(Some(id)))? else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "The journal is empty; nothing has been applied yet.",
+        )
+        .into());
+    };
+
+    let file_path = entry_path(journal_dir, id);
+    let data = match fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("There is no journal entry {}", id),
+            )
+            .into())
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    serde_json::from_str(&data).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Could not parse journal entry {}: {}",
+                file_path.display(),
+                e
+            ),
+        )
+        .into()
+    })
+}
+
+fn entry_path(journal_dir: &Path, id: u64) -> PathBuf {
+    journal_dir.join(format!("{}.json", id))
+}
+
+fn latest_id(journal_dir: &Path) -> Result<Option<u64>> {
+    let entries = match fs::read_dir(journal_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut latest = None;
+    for entry in entries {
+        let file_name = entry?.file_name();
+        let id = file_name
+            .to_str()
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|id| id.parse::<u64>().ok());
+        latest = latest.max(id);
+    }
+    Ok(latest)
+}
@@ -0,0 +1,50 @@
+//! The state model and planner behind the `moree` command.
+//!
+//! moree records why each package is installed in a JSON state file and brings the
+//! system in line with it. This crate exposes the pieces the command is built from,
+//! so other tools can read and write the state, inspect differences and build plans
+//! without going through the CLI:
+//!
+//! - [`moree_state_manager`] loads, locks and saves the state file.
+//! - [`package_manager_integration`] abstracts the package managers behind the
+//!   [`PackageManager`] trait.
+//! - [`diff`] compares the state with what is installed.
+//! - [`plan`] turns the state into the steps `moree apply` runs, and [`journal`]
+//!   records them for rollback.
+//!
+//! Nothing in the library prompts. Every fallible function returns [`Result`], so
+//! failures such as [`Error::StateParse`] or [`Error::BackendFailed`] can be matched
+//! on directly.
+//!
+//! ```no_run
+//! use moree::{BackendOptions, Plan};
+//!
+//! # fn main() -> moree::Result<()> {
+//! let backends = moree::get_backends(&["pacman".to_string()], &BackendOptions::default())?;
+//! let path = moree::get_state_file_path(&None)?;
+//! let package_states = moree::load_package_states(&path)?;
+//!
+//! let plan = Plan::build(&package_states, &backends, true, false)?;
+//! plan.print(&backends);
+//! # Ok(())
+//! # }
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod config;
+pub mod diff;
+pub mod error;
+pub mod journal;
+pub mod moree_state_manager;
+pub mod package_manager_integration;
+pub mod plan;
+pub mod protected;
+
+pub use error::{Error, Result};
+pub use moree_state_manager::{
+    get_state_file_path, load_package_states, lock_state_file, save_package_states, PackageState,
+};
+pub use package_manager_integration::{get_backends, BackendOptions, Backends, PackageManager};
+pub use plan::{Action, Plan, Step};
+pub use protected::Protected;
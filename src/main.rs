@@ -1,11 +1,12 @@
 mod commands;
-mod moree_state_manager;
-mod package_manager_integration;
 mod utils;
 
+use chrono::NaiveDate;
 use clap::{CommandFactory, Parser, Subcommand};
+use moree::error::{self, Error};
+use moree::{config, journal, moree_state_manager, package_manager_integration, protected};
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const PROGRAM_NAME: &str = "moree";
 
@@ -18,12 +19,54 @@ struct Cli {
 
     #[arg(long, value_name = "path")]
     data: Option<PathBuf>,
+
+    #[arg(long, value_name = "name")]
+    backend: Vec<String>,
+
+    #[arg(long, value_name = "[backend=]path", value_parser = parse_scoped_path)]
+    root: Vec<ScopedPath>,
+
+    #[arg(long, value_name = "[backend=]path", value_parser = parse_scoped_path)]
+    dbpath: Vec<ScopedPath>,
+}
+
+// A `--root` or `--dbpath` value, optionally limited to one backend as in
+// `pacman=/mnt`.
+#[derive(Clone)]
+struct ScopedPath {
+    backend: Option<String>,
+    path: PathBuf,
+}
+
+fn parse_scoped_path(value: &str) -> Result<ScopedPath, String> {
+    match value.split_once('=') {
+        Some((backend, path)) if package_manager_integration::is_backend(backend) => {
+            Ok(ScopedPath {
+                backend: Some(backend.to_string()),
+                path: PathBuf::from(path),
+            })
+        }
+        _ => Ok(ScopedPath {
+            backend: None,
+            path: PathBuf::from(value),
+        }),
+    }
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Add {
         packages: Vec<String>,
+        #[arg(long, group = "reason", requires = "packages")]
+        explicit: bool,
+        #[arg(long, group = "reason", requires = "packages")]
+        dep: bool,
+        #[arg(long, requires = "reason")]
+        memo: Option<String>,
+        #[arg(long, value_name = "tag", value_delimiter = ',', requires = "reason")]
+        tag: Vec<String>,
+        #[arg(long, value_name = "path", conflicts_with_all = ["packages", "reason"])]
+        from: Option<PathBuf>,
     },
     Apply {
         #[arg(long)]
@@ -32,6 +75,30 @@ enum Commands {
         with_uninstall: bool,
         #[arg(long)]
         sync: bool,
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(short, long)]
+        yes: bool,
+        #[arg(long, value_name = "path")]
+        plan_out: Option<PathBuf>,
+        #[arg(
+            long,
+            value_name = "path",
+            conflicts_with_all = ["with_install", "with_uninstall", "sync", "plan_out"]
+        )]
+        plan_in: Option<PathBuf>,
+        #[arg(
+            long,
+            conflicts_with_all = ["with_install", "with_uninstall", "sync", "plan_out", "plan_in"]
+        )]
+        resume: bool,
+    },
+    Rollback {
+        id: Option<u64>,
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(short, long)]
+        yes: bool,
     },
     Unmanaged,
     Diff {
@@ -48,43 +115,248 @@ enum Commands {
         explicit: bool,
         #[arg(short, long)]
         deps: bool,
+        #[arg(long, value_name = "tag")]
+        tag: Option<String>,
+        #[arg(long, value_name = "date")]
+        reviewed_before: Option<NaiveDate>,
+    },
+    Tags,
+    Review {
+        #[arg(long, value_name = "days")]
+        interval: Option<u32>,
+    },
+    Why {
+        package: String,
+    },
+    Orphans {
+        #[arg(long)]
+        remove: bool,
+        #[arg(long, requires = "remove")]
+        dry_run: bool,
+        #[arg(short, long, requires = "remove")]
+        yes: bool,
     },
 }
 
-fn main() -> io::Result<()> {
+fn main() {
     let args = Cli::parse();
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(args: &Cli) -> error::Result<()> {
     let state_file_path = moree_state_manager::get_state_file_path(&args.data)?;
+    if args.data.is_some() {
+        confirm_state_file_extension(&state_file_path)?;
+    }
+    let config = config::load_config()?;
+    let backend_names = if args.backend.is_empty() {
+        config.backend_names()
+    } else {
+        args.backend.clone()
+    };
+    let selection = select_backend_options(&backend_names, &args.root, &args.dbpath)?;
+    let backends = package_manager_integration::get_backends_with_options(&selection)?;
+    let modifies_state = matches!(
+        args.command,
+        Some(Commands::Add { .. })
+            | Some(Commands::Edit { .. })
+            | Some(Commands::Review { .. })
+            | Some(Commands::Orphans { remove: true, .. })
+    );
+    let _state_lock = moree_state_manager::lock_state_file(&state_file_path, modifies_state)?;
     let mut package_states = moree_state_manager::load_package_states(&state_file_path)?;
 
-    let result = match &args.command {
-        Some(Commands::Add { packages }) => {
-            commands::add::run(&mut package_states, &state_file_path, packages)
+    match &args.command {
+        Some(Commands::Add {
+            packages,
+            explicit,
+            dep,
+            memo,
+            tag,
+            from,
+        }) => {
+            if let Some(source) = from {
+                return commands::add::run_from(
+                    &mut package_states,
+                    &state_file_path,
+                    &backends,
+                    source,
+                );
+            }
+            let decision = (*explicit || *dep).then(|| commands::add::Decision {
+                explicit: *explicit,
+                memo: memo.clone(),
+                tags: moree_state_manager::normalize_tags(tag.iter().cloned()),
+            });
+            commands::add::run(
+                &mut package_states,
+                &state_file_path,
+                &backends,
+                packages,
+                decision.as_ref(),
+            )
         }
         Some(Commands::Apply {
             with_install,
             with_uninstall,
             sync,
-        }) => commands::apply::run(&package_states, *with_install, *with_uninstall, *sync),
-        Some(Commands::Unmanaged) => commands::unmanaged::run(&package_states),
-        Some(Commands::Diff { all }) => commands::diff::run(&package_states, *all),
+            dry_run,
+            yes,
+            plan_out,
+            plan_in,
+            resume,
+        }) => commands::apply::run(
+            &package_states,
+            &backends,
+            &journal::get_journal_dir(&state_file_path),
+            &commands::apply::ApplyOptions {
+                with_install: *with_install || *sync,
+                with_uninstall: *with_uninstall || *sync,
+                dry_run: *dry_run,
+                yes: *yes,
+                plan_out: plan_out.clone(),
+                plan_in: plan_in.clone(),
+                resume: *resume,
+                protected: protected::Protected::new(&config.protected),
+            },
+        ),
+        Some(Commands::Rollback { id, dry_run, yes }) => commands::rollback::run(
+            &backends,
+            &journal::get_journal_dir(&state_file_path),
+            *id,
+            *dry_run,
+            *yes,
+            &protected::Protected::new(&config.protected),
+        ),
+        Some(Commands::Unmanaged) => commands::unmanaged::run(&package_states, &backends),
+        Some(Commands::Diff { all }) => commands::diff::run(&package_states, &backends, *all),
         Some(Commands::Edit { package }) => {
-            commands::edit::run(&mut package_states, &state_file_path, package)
+            commands::edit::run(&mut package_states, &state_file_path, &backends, package)
         }
         Some(Commands::Query {
             information,
             explicit,
             deps,
-        }) => commands::query::run(&package_states, *information, *explicit, *deps),
+            tag,
+            reviewed_before,
+        }) => commands::query::run(
+            &package_states,
+            &backends,
+            *information,
+            *explicit,
+            *deps,
+            tag.as_deref(),
+            *reviewed_before,
+        ),
+        Some(Commands::Tags) => commands::tags::run(&package_states, &backends),
+        Some(Commands::Orphans {
+            remove,
+            dry_run,
+            yes,
+        }) => commands::orphans::run(
+            &mut package_states,
+            &state_file_path,
+            &backends,
+            &journal::get_journal_dir(&state_file_path),
+            &commands::orphans::OrphansOptions {
+                remove: *remove,
+                dry_run: *dry_run,
+                yes: *yes,
+                protected: protected::Protected::new(&config.protected),
+            },
+        ),
+        Some(Commands::Why { package }) => commands::why::run(&package_states, &backends, package),
+        Some(Commands::Review { interval }) => commands::review::run(
+            &mut package_states,
+            &state_file_path,
+            &backends,
+            interval
+                .or(config.review_interval_days)
+                .unwrap_or(commands::review::DEFAULT_REVIEW_INTERVAL_DAYS),
+        ),
         None => {
             println!("{}", Cli::command().render_long_help());
             Ok(())
         }
+    }
+}
+
+// Pairs every selected backend with its `--root` and `--dbpath`. A path without a
+// backend applies to the only selected backend; with several, each path has to name
+// the backend it is for, so that one system's root is not passed to all of them.
+fn select_backend_options(
+    names: &[String],
+    roots: &[ScopedPath],
+    db_paths: &[ScopedPath],
+) -> io::Result<Vec<(String, package_manager_integration::BackendOptions)>> {
+    let names = if names.is_empty() {
+        vec![package_manager_integration::DEFAULT_BACKEND.to_string()]
+    } else {
+        names.to_vec()
     };
 
-    if let Err(e) = result {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    let path_for = |flag: &str, values: &[ScopedPath], name: &str| -> io::Result<Option<PathBuf>> {
+        let mut found = None;
+        for value in values {
+            match &value.backend {
+                Some(backend) if !names.contains(backend) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} names the {} backend, which is not selected",
+                            flag, backend
+                        ),
+                    ));
+                }
+                Some(backend) if backend == name => found = Some(value.path.clone()),
+                Some(_) => {}
+                None if names.len() > 1 => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} would apply to every selected backend; name the backend it is for, as in {} {}={}",
+                            flag,
+                            flag,
+                            names[0],
+                            value.path.display()
+                        ),
+                    ));
+                }
+                None => found = Some(value.path.clone()),
+            }
+        }
+        Ok(found)
+    };
+
+    names
+        .iter()
+        .map(|name| {
+            let options = package_manager_integration::BackendOptions {
+                root: path_for("--root", roots, name)?,
+                db_path: path_for("--dbpath", db_paths, name)?,
+            };
+            Ok((name.clone(), options))
+        })
+        .collect()
+}
+
+// A custom state file without a .json extension is likely a typo; check before using it.
+fn confirm_state_file_extension(path: &Path) -> error::Result<()> {
+    if path.extension().and_then(|ext| ext.to_str()) == Some("json") {
+        return Ok(());
     }
 
+    utils::require_terminal(&format!(
+        "{} does not have a .json extension; use a path ending in .json",
+        path.display()
+    ))?;
+    println!("The provided path does not have a .json extension. ");
+    if !utils::confirm_prompt("Are you sure you want to use this path? (y/n)", false)? {
+        return Err(Error::UserAborted);
+    }
     Ok(())
 }
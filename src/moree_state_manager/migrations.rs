@@ -0,0 +1,72 @@
+use super::{qualified_name, NAMESPACE_SEPARATOR};
+use serde_json::{json, Map, Value};
+
+pub const CURRENT_VERSION: u64 = 2;
+
+// Before entries were namespaced, moree only managed paru, so un-namespaced entries
+// belong to pacman no matter which backends are selected now.
+const LEGACY_NAMESPACE: &str = "pacman";
+
+// MIGRATIONS[n] upgrades a document from version n + 1 to version n + 2.
+type Migration = fn(Value) -> Result<Value, String>;
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+// Brings a parsed state file up to CURRENT_VERSION. Errors describe what is wrong with
+// the document; the caller reports them as a state file that could not be parsed.
+pub fn migrate(mut document: Value) -> Result<Value, String> {
+    let mut version = detect_version(&document)?;
+
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "The state file uses schema version {}, but this moree only supports up to version {}. Please upgrade moree.",
+            version, CURRENT_VERSION
+        ));
+    }
+
+    while version < CURRENT_VERSION {
+        document = MIGRATIONS[(version - 1) as usize](document)?;
+        version += 1;
+    }
+
+    Ok(document)
+}
+
+// Version 1 files are a bare map from package name to state, so a top-level numeric
+// `version` can only come from a versioned document. Versions start at 1.
+fn detect_version(document: &Value) -> Result<u64, String> {
+    match document.get("version") {
+        Some(Value::Number(number)) => number
+            .as_u64()
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| format!("invalid schema version {}", number)),
+        _ => Ok(1),
+    }
+}
+
+// Version 1 was a map, optionally with namespaced keys. It is usually bare, but may
+// already be wrapped as `{"version": 1, "packages": {...}}`. Version 2 always wraps it
+// and namespaces every key.
+fn migrate_v1_to_v2(document: Value) -> Result<Value, String> {
+    let entries = match document {
+        Value::Object(mut document) if document.get("version").is_some_and(Value::is_number) => {
+            document.remove("packages")
+        }
+        document => Some(document),
+    };
+    let Some(Value::Object(entries)) = entries else {
+        return Err("expected a JSON object of packages".to_string());
+    };
+
+    let packages: Map<String, Value> = entries
+        .into_iter()
+        .map(|(key, state)| {
+            if key.contains(NAMESPACE_SEPARATOR) {
+                (key, state)
+            } else {
+                (qualified_name(LEGACY_NAMESPACE, &key), state)
+            }
+        })
+        .collect();
+
+    Ok(json!({ "version": 2, "packages": packages }))
+}
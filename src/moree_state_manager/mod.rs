@@ -0,0 +1,302 @@
+//! The state file: which packages moree manages, and why each one is installed.
+
+mod migrations;
+
+use crate::error::{Error, Result};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const PROGRAM_NAME: &str = "moree";
+const DEFAULT_STATE_FILE: &str = "state.json";
+/// Separates the namespace from the package name in state keys, as in `cargo:ripgrep`.
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// What moree records about a package.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct PackageState {
+    /// Whether the package should be installed explicitly rather than as a dependency.
+    pub explicit: bool,
+    /// Why the package is installed, in the user's words.
+    pub memo: Option<String>,
+    /// Labels such as `work` or `fonts`, sorted and without duplicates. Omitted from
+    /// the state file when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// When the entry was first recorded. Entries from before timestamps were kept
+    /// have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub added_at: Option<DateTime<Utc>>,
+    /// When the reason, memo or tags last changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified_at: Option<DateTime<Utc>>,
+    /// When the reason was last confirmed, whether or not it changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl PackageState {
+    /// Marks the entry as confirmed at `now`, replacing `previous`. `added_at` carries
+    /// over from `previous`, and `modified_at` only moves when the reason, memo or tags
+    /// differ from it.
+    pub fn stamp(&mut self, previous: Option<&PackageState>, now: DateTime<Utc>) {
+        self.added_at = previous.map_or(Some(now), |previous| previous.added_at);
+        self.modified_at = match previous {
+            Some(previous) if previous.same_reason(self) => previous.modified_at,
+            _ => Some(now),
+        };
+        self.reviewed_at = Some(now);
+    }
+
+    /// Whether both entries record the same reason, memo and tags.
+    pub fn same_reason(&self, other: &PackageState) -> bool {
+        self.explicit == other.explicit && self.memo == other.memo && self.tags == other.tags
+    }
+}
+
+// `P` is an owned map when loading and a borrowed one when saving.
+#[derive(Serialize, Deserialize, Debug)]
+struct StateDocument<P> {
+    version: u64,
+    packages: P,
+}
+
+/// The state file to use: `custom_path` if given, otherwise `state.json` in moree's
+/// data directory. Missing parent directories are created.
+pub fn get_state_file_path(custom_path: &Option<PathBuf>) -> Result<PathBuf> {
+    match custom_path {
+        Some(path) => get_custom_state_file_path(path),
+        None => get_default_state_file_path(),
+    }
+}
+
+fn get_custom_state_file_path(custom_path: &Path) -> Result<PathBuf> {
+    // Ensure the path isn't a directory
+    if custom_path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The provided path is a directory, not a file.",
+        )
+        .into());
+    }
+
+    // Create parent directories if they don't exist
+    if let Some(parent_dir) = custom_path.parent() {
+        fs::create_dir_all(parent_dir)?;
+    }
+
+    Ok(custom_path.to_path_buf())
+}
+
+fn get_default_state_file_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir()
+        .ok_or_else(|| io::Error::other("Could not determine data directory."))?
+        .join(PROGRAM_NAME);
+
+    fs::create_dir_all(&data_dir)?;
+    Ok(data_dir.join(DEFAULT_STATE_FILE))
+}
+
+/// The state key of a package, such as `cargo:ripgrep`.
+pub fn qualified_name(namespace: &str, package_name: &str) -> String {
+    format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, package_name)
+}
+
+/// Splits a state key into namespace and package name. A key without a namespace
+/// has an empty one.
+pub fn split_qualified_name(key: &str) -> (&str, &str) {
+    key.split_once(NAMESPACE_SEPARATOR).unwrap_or(("", key))
+}
+
+/// The entries of one namespace, keyed by bare package name.
+pub fn package_states_in(
+    package_states: &HashMap<String, PackageState>,
+    namespace: &str,
+) -> HashMap<String, PackageState> {
+    package_states
+        .iter()
+        .filter_map(|(key, state)| {
+            let (key_namespace, package_name) = split_qualified_name(key);
+            (key_namespace == namespace).then(|| (package_name.to_string(), state.clone()))
+        })
+        .collect()
+}
+
+/// Trims and sorts tags, dropping empty ones and duplicates.
+pub fn normalize_tags<I: IntoIterator<Item = String>>(tags: I) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Parses a comma-separated list of tags, such as `work, fonts`.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    normalize_tags(text.split(',').map(str::to_string))
+}
+
+/// Resolves a package given on the command line to its state key. A bare name is
+/// looked up in the given namespaces and has to be unambiguous.
+pub fn find_package_key(
+    package_states: &HashMap<String, PackageState>,
+    namespaces: &[&str],
+    package: &str,
+) -> Result<Option<String>> {
+    if package.contains(NAMESPACE_SEPARATOR) {
+        return Ok(package_states
+            .contains_key(package)
+            .then(|| package.to_string()));
+    }
+
+    let mut found: Vec<String> = namespaces
+        .iter()
+        .map(|namespace| qualified_name(namespace, package))
+        .filter(|key| package_states.contains_key(key))
+        .collect();
+
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop()),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "{} is managed in several namespaces; use one of: {}",
+                package,
+                found.join(", ")
+            ),
+        )
+        .into()),
+    }
+}
+
+/// Older layouts are migrated on load and written back in the current layout on the
+/// next save. Entries written before state keys were namespaced, when moree only
+/// managed paru, are moved into the `pacman` namespace.
+pub fn load_package_states(file_path: &Path) -> Result<HashMap<String, PackageState>> {
+    let data = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()), // Return empty HashMap if file not found.
+        Err(err) => return Err(err.into()),
+    };
+
+    let document = serde_json::from_str(&data).map_err(|e| parse_error(file_path, e))?;
+    let document = migrations::migrate(document).map_err(|message| Error::StateParse {
+        path: file_path.to_path_buf(),
+        line: 0,
+        column: 0,
+        message,
+    })?;
+    let document: StateDocument<HashMap<String, PackageState>> =
+        serde_json::from_value(document).map_err(|e| parse_error(file_path, e))?;
+
+    Ok(document.packages)
+}
+
+fn parse_error(file_path: &Path, e: serde_json::Error) -> Error {
+    // serde_json appends the position to its message; it is reported separately.
+    let message = e.to_string();
+    let position = format!(" at line {} column {}", e.line(), e.column());
+    Error::StateParse {
+        path: file_path.to_path_buf(),
+        line: e.line(),
+        column: e.column(),
+        message: message
+            .strip_suffix(&position)
+            .unwrap_or(&message)
+            .to_string(),
+    }
+}
+
+/// An advisory lock on the state file, released when dropped. Commands that change
+/// the state hold it exclusively from load to save; read-only commands share it.
+pub struct StateLock {
+    _file: File,
+}
+
+/// Locks the state file, exclusively for commands that change it and shared for those
+/// that only read it. Fails right away if another moree holds a conflicting lock.
+pub fn lock_state_file(file_path: &Path, exclusive: bool) -> Result<StateLock> {
+    // The state file itself is replaced on every save, so the lock lives next to it.
+    let mut lock_path = OsString::from(file_path.as_os_str());
+    lock_path.push(".lock");
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+
+    let result = if exclusive {
+        file.try_lock()
+    } else {
+        file.try_lock_shared()
+    };
+
+    match result {
+        Ok(()) => Ok(StateLock { _file: file }),
+        Err(TryLockError::WouldBlock) => Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            format!(
+                "{} is in use by another moree process. Try again once it has finished.",
+                file_path.display()
+            ),
+        )
+        .into()),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+/// Writes the state file in the current layout. The file is replaced atomically, so
+/// an interrupted save never leaves a truncated file behind.
+pub fn save_package_states(
+    file_path: &Path,
+    package_states: &HashMap<String, PackageState>,
+) -> Result<()> {
+    let document = StateDocument {
+        version: migrations::CURRENT_VERSION,
+        packages: package_states,
+    };
+    let data = serde_json::to_string_pretty(&document).map_err(io::Error::from)?; // Pretty print for readability
+    write_atomically(file_path, data.as_bytes())
+}
+
+// Writes to a temporary file in the same directory and renames it over the target, so
+// an interrupted save leaves either the old or the new file behind, never a truncated one.
+fn write_atomically(file_path: &Path, data: &[u8]) -> Result<()> {
+    // Write through symlinks, e.g. a state file kept in a dotfiles repository.
+    let file_path = fs::canonicalize(file_path).unwrap_or_else(|_| file_path.to_path_buf());
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid state file path."))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(format!(".{}.tmp", process::id()));
+    let temp_path = file_path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, &file_path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result?;
+
+    // Persist the rename itself.
+    if let Some(parent_dir) = file_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(parent_dir)?.sync_all()?;
+    }
+
+    Ok(())
+}
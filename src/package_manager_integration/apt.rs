@@ -0,0 +1,368 @@
+use super::{cached, run_interactive, BackendOptions, PackageManager};
+use crate::error::Result;
+use colored::*;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DEFAULT_DPKG_PATH: &str = "var/lib/dpkg";
+const EXTENDED_STATES_PATH: &str = "var/lib/apt/extended_states";
+
+#[derive(Debug, Clone, Default)]
+pub struct DebianPackage {
+    pub name: String,
+    pub version: String,
+    pub architecture: String,
+    pub description: String,
+    pub depends: String,
+    pub pre_depends: String,
+    pub recommends: String,
+    pub suggests: String,
+    pub provides: String,
+    pub installed_size: Option<u64>,
+    pub explicit: bool,
+    pub required_by: Vec<String>,
+    pub recommended_by: Vec<String>,
+    pub suggested_by: Vec<String>,
+}
+
+// Reads dpkg's status file and apt's auto-installed marks, which map directly onto
+// explicit and dependency packages. Changes go through apt-get and apt-mark.
+pub struct Apt {
+    status_path: PathBuf,
+    extended_states_path: PathBuf,
+    apt_args: Vec<OsString>,
+    packages: OnceCell<BTreeMap<String, DebianPackage>>,
+}
+
+impl Apt {
+    pub fn new(options: &BackendOptions) -> Self {
+        let root = options.root.clone().unwrap_or_else(|| PathBuf::from("/"));
+        let dpkg_path = options
+            .db_path
+            .clone()
+            .unwrap_or_else(|| root.join(DEFAULT_DPKG_PATH));
+
+        let mut apt_args = Vec::new();
+        if let Some(root) = &options.root {
+            let mut arg = OsString::from("Dir=");
+            arg.push(root);
+            apt_args.push(OsString::from("-o"));
+            apt_args.push(arg);
+        }
+        if let Some(db_path) = &options.db_path {
+            let mut arg = OsString::from("Dir::State::status=");
+            arg.push(db_path.join("status"));
+            apt_args.push(OsString::from("-o"));
+            apt_args.push(arg);
+        }
+
+        Self {
+            status_path: dpkg_path.join("status"),
+            extended_states_path: root.join(EXTENDED_STATES_PATH),
+            apt_args,
+            packages: OnceCell::new(),
+        }
+    }
+
+    fn packages(&self) -> Result<&BTreeMap<String, DebianPackage>> {
+        cached(&self.packages, || {
+            load_installed_packages(&self.status_path, &self.extended_states_path)
+        })
+    }
+
+    fn list_by_reason(&self, explicit: bool) -> Result<Vec<String>> {
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| package.explicit == explicit)
+            .map(|package| package.name.clone())
+            .collect())
+    }
+
+    fn run_command(&self, program: &str, args: &[&str], packages: &[String]) -> Result<()> {
+        run_interactive(
+            Command::new("sudo")
+                .arg(program)
+                .args(&self.apt_args)
+                .args(args)
+                .args(packages),
+        )
+    }
+}
+
+impl PackageManager for Apt {
+    fn name(&self) -> &str {
+        "apt"
+    }
+
+    fn namespace(&self) -> &str {
+        "apt"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        self.list_by_reason(true)
+    }
+
+    fn supports_install_reasons(&self) -> bool {
+        true
+    }
+
+    fn list_deps(&self) -> Result<Vec<String>> {
+        self.list_by_reason(false)
+    }
+
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| !package.required_by.is_empty())
+            .map(|package| (package.name.clone(), package.required_by.clone()))
+            .collect())
+    }
+
+    fn list_orphans(&self) -> Result<Vec<String>> {
+        // `apt autoremove` keeps packages that an installed package recommends or
+        // suggests.
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| {
+                !package.explicit
+                    && package.required_by.is_empty()
+                    && package.recommended_by.is_empty()
+                    && package.suggested_by.is_empty()
+            })
+            .map(|package| package.name.clone())
+            .collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let package = self.packages()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Package {} is not installed", package_name),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Package         : {}", package.name);
+        println!("Version         : {}", package.version);
+        println!("Architecture    : {}", package.architecture);
+        println!("Description     : {}", package.description);
+        println!(
+            "Depends         : {}",
+            Some(package.depends.as_str())
+                .filter(|depends| !depends.is_empty())
+                .unwrap_or("None")
+        );
+        println!(
+            "Installed-Size  : {}",
+            package
+                .installed_size
+                .map_or("None".to_string(), |size| format!("{} KiB", size))
+        );
+        println!(
+            "Install Reason  : {}",
+            if package.explicit {
+                "Manually installed"
+            } else {
+                "Automatically installed"
+            }
+        );
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("apt-get", &["install"], packages)
+    }
+
+    // apt-get install always records packages as manually installed; apt-mark auto
+    // demotes them afterwards.
+    fn install_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.install_packages(packages)?;
+        self.mark_packages_as_deps(packages)
+    }
+
+    fn mark_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.run_command("apt-mark", &["auto"], packages)
+    }
+
+    fn mark_packages_as_explicit(&self, packages: &[String]) -> Result<()> {
+        self.run_command("apt-mark", &["manual"], packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("apt-get", &["remove"], packages)
+    }
+}
+
+pub fn load_installed_packages(
+    status_path: &Path,
+    extended_states_path: &Path,
+) -> Result<BTreeMap<String, DebianPackage>> {
+    let status = fs::read_to_string(status_path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Could not read {}: {}", status_path.display(), e),
+        )
+    })?;
+    // A system where nothing was ever installed automatically has no extended_states.
+    let extended_states = match fs::read_to_string(extended_states_path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err.into()),
+    };
+
+    let auto_installed: HashSet<String> = parse_paragraphs(&extended_states)
+        .into_iter()
+        .filter(|fields| fields.get("Auto-Installed").map(String::as_str) == Some("1"))
+        .filter_map(|mut fields| fields.remove("Package"))
+        .collect();
+
+    let mut packages = BTreeMap::new();
+    for mut fields in parse_paragraphs(&status) {
+        let installed = fields
+            .get("Status")
+            .is_some_and(|status| status.ends_with(" installed"));
+        let Some(name) = fields.remove("Package").filter(|_| installed) else {
+            continue;
+        };
+
+        packages.insert(
+            name.clone(),
+            DebianPackage {
+                explicit: !auto_installed.contains(&name),
+                version: fields.remove("Version").unwrap_or_default(),
+                architecture: fields.remove("Architecture").unwrap_or_default(),
+                description: fields.remove("Description").unwrap_or_default(),
+                depends: fields.remove("Depends").unwrap_or_default(),
+                pre_depends: fields.remove("Pre-Depends").unwrap_or_default(),
+                recommends: fields.remove("Recommends").unwrap_or_default(),
+                suggests: fields.remove("Suggests").unwrap_or_default(),
+                provides: fields.remove("Provides").unwrap_or_default(),
+                installed_size: fields
+                    .get("Installed-Size")
+                    .and_then(|size| size.parse().ok()),
+                name,
+                ..Default::default()
+            },
+        );
+    }
+
+    resolve_required_by(&mut packages);
+    Ok(packages)
+}
+
+// Fills in which installed packages depend on, recommend or suggest each package. Every
+// installed alternative of a dependency such as `a | b` counts, as do packages that
+// provide it.
+fn resolve_required_by(packages: &mut BTreeMap<String, DebianPackage>) {
+    let mut providers: HashMap<&str, Vec<String>> = HashMap::new();
+    for package in packages.values() {
+        providers
+            .entry(package.name.as_str())
+            .or_default()
+            .push(package.name.clone());
+        for provided in relation_names(&package.provides) {
+            providers
+                .entry(provided)
+                .or_default()
+                .push(package.name.clone());
+        }
+    }
+
+    let required_by = reverse_relations(packages, &providers, |package| {
+        relation_names(&package.depends)
+            .chain(relation_names(&package.pre_depends))
+            .collect()
+    });
+    let recommended_by = reverse_relations(packages, &providers, |package| {
+        relation_names(&package.recommends).collect()
+    });
+    let suggested_by = reverse_relations(packages, &providers, |package| {
+        relation_names(&package.suggests).collect()
+    });
+    for package in packages.values_mut() {
+        package.required_by = required_by.get(&package.name).cloned().unwrap_or_default();
+        package.recommended_by = recommended_by
+            .get(&package.name)
+            .cloned()
+            .unwrap_or_default();
+        package.suggested_by = suggested_by.get(&package.name).cloned().unwrap_or_default();
+    }
+}
+
+// Maps each provider to the sorted packages, other than itself, whose `relations`
+// name it.
+fn reverse_relations<'a>(
+    packages: &'a BTreeMap<String, DebianPackage>,
+    providers: &HashMap<&str, Vec<String>>,
+    relations: impl Fn(&'a DebianPackage) -> Vec<&'a str>,
+) -> HashMap<String, Vec<String>> {
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+    for package in packages.values() {
+        let names = relations(package);
+        for provider in names
+            .iter()
+            .filter_map(|name| providers.get(name))
+            .flatten()
+        {
+            if *provider != package.name {
+                dependents
+                    .entry(provider.clone())
+                    .or_default()
+                    .push(package.name.clone());
+            }
+        }
+    }
+
+    for names in dependents.values_mut() {
+        names.sort();
+        names.dedup();
+    }
+    dependents
+}
+
+// The package names in a relationship field such as
+// `libc6 (>= 2.34), default-mta | mail-transport-agent`, without versions or
+// architecture qualifiers.
+fn relation_names(field: &str) -> impl Iterator<Item = &str> {
+    field
+        .split([',', '|'])
+        .filter_map(|relation| relation.split_whitespace().next())
+        .map(|name| name.split(':').next().unwrap_or(name))
+}
+
+// Splits a deb822 control file into paragraphs of fields. Continuation lines are
+// dropped, so multi-line fields such as Description keep only their first line.
+fn parse_paragraphs(content: &str) -> Vec<HashMap<String, String>> {
+    let mut paragraphs = Vec::new();
+    let mut fields = HashMap::new();
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                paragraphs.push(std::mem::take(&mut fields));
+            }
+            continue;
+        }
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            fields.insert(key.to_string(), value.trim().to_string());
+        }
+    }
+    if !fields.is_empty() {
+        paragraphs.push(fields);
+    }
+
+    paragraphs
+}
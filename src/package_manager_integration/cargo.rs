@@ -0,0 +1,162 @@
+use super::{cached, run_interactive, BackendOptions, PackageManager};
+use crate::error::Result;
+use colored::*;
+use serde::Deserialize;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone)]
+pub struct CargoInstall {
+    pub name: String,
+    pub version: String,
+    pub source: String,
+    pub bins: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct CratesV2 {
+    installs: HashMap<String, CratesV2Install>,
+}
+
+#[derive(Deserialize)]
+struct CratesV2Install {
+    #[serde(default)]
+    bins: Vec<String>,
+}
+
+// Binaries installed with `cargo install`, read from cargo's own `.crates2.json`.
+// cargo has no notion of dependencies, so every crate counts as explicit.
+pub struct Cargo {
+    crates_file: PathBuf,
+    root_args: Vec<OsString>,
+    installs: OnceCell<BTreeMap<String, CargoInstall>>,
+}
+
+impl Cargo {
+    pub fn new(options: &BackendOptions) -> Result<Self> {
+        let root = match &options.root {
+            Some(root) => root.clone(),
+            None => cargo_home()?,
+        };
+
+        let mut root_args = Vec::new();
+        if let Some(root) = &options.root {
+            root_args.push(OsString::from("--root"));
+            root_args.push(root.clone().into_os_string());
+        }
+
+        Ok(Self {
+            crates_file: root.join(".crates2.json"),
+            root_args,
+            installs: OnceCell::new(),
+        })
+    }
+
+    fn installs(&self) -> Result<&BTreeMap<String, CargoInstall>> {
+        cached(&self.installs, || load_installs(&self.crates_file))
+    }
+
+    fn run_command(&self, subcommand: &str, packages: &[String]) -> Result<()> {
+        run_interactive(
+            Command::new("cargo")
+                .arg(subcommand)
+                .args(&self.root_args)
+                .args(packages),
+        )
+    }
+}
+
+impl PackageManager for Cargo {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn namespace(&self) -> &str {
+        "cargo"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.installs()?.keys().cloned().collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let install = self.installs()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Crate {} is not installed", package_name),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", install.name);
+        println!("Version         : {}", install.version);
+        println!("Source          : {}", install.source);
+        println!("Binaries        : {}", install.bins.join("  "));
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("install", packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("uninstall", packages)
+    }
+}
+
+fn cargo_home() -> Result<PathBuf> {
+    if let Some(cargo_home) = std::env::var_os("CARGO_HOME") {
+        return Ok(PathBuf::from(cargo_home));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".cargo"))
+        .ok_or_else(|| io::Error::other("Could not determine home directory.").into())
+}
+
+fn load_installs(crates_file: &Path) -> Result<BTreeMap<String, CargoInstall>> {
+    let data = match fs::read_to_string(crates_file) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()), // Nothing installed yet.
+        Err(err) => return Err(err.into()),
+    };
+
+    let crates: CratesV2 = serde_json::from_str(&data).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse {}: {}", crates_file.display(), e),
+        )
+    })?;
+
+    let mut installs = BTreeMap::new();
+    for (package_id, install) in crates.installs {
+        // Package ids look like `ripgrep 14.1.0 (registry+https://...)`.
+        let mut parts = package_id.splitn(3, ' ');
+        let name = parts.next().unwrap_or_default().to_string();
+        let version = parts.next().unwrap_or_default().to_string();
+        let source = parts
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .to_string();
+
+        installs.insert(
+            name.clone(),
+            CargoInstall {
+                name,
+                version,
+                source,
+                bins: install.bins,
+            },
+        );
+    }
+
+    Ok(installs)
+}
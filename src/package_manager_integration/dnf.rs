@@ -0,0 +1,168 @@
+use super::{cached, run_interactive, run_query, unsupported, BackendOptions, PackageManager};
+use crate::error::Result;
+use colored::*;
+use std::cell::OnceCell;
+use std::collections::{BTreeSet, HashMap};
+use std::ffi::OsString;
+use std::process::Command;
+
+// Uses dnf's own bookkeeping: user-installed packages are explicit, everything else
+// that is installed was pulled in as a dependency.
+pub struct Dnf {
+    root_args: Vec<OsString>,
+    user_installed: OnceCell<BTreeSet<String>>,
+    installed: OnceCell<BTreeSet<String>>,
+}
+
+impl Dnf {
+    pub fn new(options: &BackendOptions) -> Self {
+        let mut root_args = Vec::new();
+        if let Some(root) = &options.root {
+            let mut arg = OsString::from("--installroot=");
+            arg.push(root);
+            root_args.push(arg);
+        }
+
+        Self {
+            root_args,
+            user_installed: OnceCell::new(),
+            installed: OnceCell::new(),
+        }
+    }
+
+    fn user_installed(&self) -> Result<&BTreeSet<String>> {
+        cached(&self.user_installed, || self.repoquery("--userinstalled"))
+    }
+
+    fn installed(&self) -> Result<&BTreeSet<String>> {
+        cached(&self.installed, || self.repoquery("--installed"))
+    }
+
+    fn repoquery(&self, filter: &str) -> Result<BTreeSet<String>> {
+        let output = run_query(Command::new("dnf").args(&self.root_args).args([
+            "repoquery",
+            "--quiet",
+            filter,
+            "--queryformat",
+            "%{name}\\n",
+        ]))?;
+
+        Ok(parse_package_names(&output))
+    }
+
+    fn run_command(&self, args: &[&str], packages: &[String]) -> Result<()> {
+        run_interactive(
+            Command::new("sudo")
+                .arg("dnf")
+                .args(&self.root_args)
+                .args(args)
+                .args(packages),
+        )
+    }
+}
+
+impl PackageManager for Dnf {
+    fn name(&self) -> &str {
+        "dnf"
+    }
+
+    fn namespace(&self) -> &str {
+        "dnf"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.user_installed()?.iter().cloned().collect())
+    }
+
+    fn supports_install_reasons(&self) -> bool {
+        true
+    }
+
+    fn list_deps(&self) -> Result<Vec<String>> {
+        let explicit = self.user_installed()?;
+        Ok(self
+            .installed()?
+            .iter()
+            .filter(|package| !explicit.contains(*package))
+            .cloned()
+            .collect())
+    }
+
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        Err(unsupported(self.name(), "reverse dependency lookups"))
+    }
+
+    fn list_orphans(&self) -> Result<Vec<String>> {
+        Ok(self.repoquery("--unneeded")?.into_iter().collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let output = run_query(Command::new("dnf").args(&self.root_args).args([
+            "info",
+            "--quiet",
+            "--installed",
+            package_name,
+        ]))?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("{}", output);
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["install"], packages)
+    }
+
+    // dnf install marks every package it is given as user-installed, and only
+    // `dnf mark remove` turns that reason into a dependency.
+    fn install_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.install_packages(packages)?;
+        self.mark_packages_as_deps(packages)
+    }
+
+    fn mark_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["mark", "remove"], packages)
+    }
+
+    fn mark_packages_as_explicit(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["mark", "install"], packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["remove"], packages)
+    }
+}
+
+// dnf 4 prints an empty line after every entry for the trailing `\n` in the query
+// format, dnf 5 does not; packages installed for several architectures repeat.
+fn parse_package_names(output: &str) -> BTreeSet<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_repoquery_output() {
+        let explicit = parse_package_names(include_str!(
+            "../../tests/fixtures/dnf/repoquery-userinstalled.txt"
+        ));
+        let installed = parse_package_names(include_str!(
+            "../../tests/fixtures/dnf/repoquery-installed.txt"
+        ));
+
+        assert_eq!(
+            explicit.iter().collect::<Vec<_>>(),
+            ["firefox", "git", "vim-enhanced"]
+        );
+        assert_eq!(installed.len(), 7);
+        assert!(explicit.is_subset(&installed));
+    }
+}
@@ -0,0 +1,218 @@
+use super::{BackendOptions, PackageManager, FAKE_BACKEND_VAR};
+use crate::error::{Error, Result};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// A file-backed stand-in for a real package manager. It keeps the installed packages
+// in a JSON file and records every operation it would have run, so commands can be
+// exercised without touching the system. It is only available when the test suite
+// asks for it, so a typo in a config can never turn `apply` into a no-op.
+pub struct Fake {
+    file_path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(default)]
+struct FakeSystem {
+    explicit: Vec<String>,
+    deps: Vec<String>,
+    groups: HashMap<String, Vec<String>>,
+    // The packages each package depends on.
+    depends: HashMap<String, Vec<String>>,
+    // Operations on these packages fail, as if the package manager had errored out.
+    failing: Vec<String>,
+    operations: Vec<FakeOperation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct FakeOperation {
+    operation: String,
+    packages: Vec<String>,
+}
+
+pub(super) fn enabled() -> bool {
+    std::env::var_os(FAKE_BACKEND_VAR).is_some_and(|value| value == "1")
+}
+
+impl Fake {
+    pub fn new(options: &BackendOptions) -> Result<Self> {
+        let file_path = options.db_path.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The fake backend needs --dbpath pointing at its JSON file.",
+            )
+        })?;
+        Ok(Self { file_path })
+    }
+
+    fn load(&self) -> Result<FakeSystem> {
+        let data = match fs::read_to_string(&self.file_path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(FakeSystem::default()),
+            Err(err) => return Err(err.into()),
+        };
+
+        serde_json::from_str(&data).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not parse fake backend file: {}", e),
+            )
+            .into()
+        })
+    }
+
+    fn record(
+        &self,
+        operation: &str,
+        packages: &[String],
+        update: impl Fn(&mut FakeSystem, &String),
+    ) -> Result<()> {
+        let mut system = self.load()?;
+        if let Some(package) = packages.iter().find(|p| system.failing.contains(p)) {
+            return Err(failed(
+                operation,
+                packages,
+                format!("{} is set to fail", package),
+            ));
+        }
+        for package in packages {
+            if !operation.starts_with("install") && !system.is_installed(package) {
+                return Err(failed(
+                    operation,
+                    packages,
+                    format!("{} is not installed", package),
+                ));
+            }
+            update(&mut system, package);
+        }
+        system.operations.push(FakeOperation {
+            operation: operation.to_string(),
+            packages: packages.to_vec(),
+        });
+
+        let data = serde_json::to_string_pretty(&system).map_err(io::Error::from)?;
+        Ok(fs::write(&self.file_path, data)?)
+    }
+}
+
+// Fails the way a real package manager command would.
+fn failed(operation: &str, packages: &[String], stderr: String) -> Error {
+    Error::BackendFailed {
+        command: format!("fake {} {}", operation, packages.join(" ")),
+        exit_code: Some(1),
+        stderr,
+    }
+}
+
+impl FakeSystem {
+    fn is_installed(&self, package: &String) -> bool {
+        self.explicit.contains(package) || self.deps.contains(package)
+    }
+
+    fn forget(&mut self, package: &String) {
+        self.explicit.retain(|p| p != package);
+        self.deps.retain(|p| p != package);
+    }
+}
+
+impl PackageManager for Fake {
+    fn name(&self) -> &str {
+        "fake"
+    }
+
+    fn namespace(&self) -> &str {
+        "fake"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.load()?.explicit)
+    }
+
+    fn supports_install_reasons(&self) -> bool {
+        true
+    }
+
+    fn list_deps(&self) -> Result<Vec<String>> {
+        Ok(self.load()?.deps)
+    }
+
+    fn package_groups(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self.load()?.groups)
+    }
+
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        let system = self.load()?;
+        let mut required_by: HashMap<String, Vec<String>> = HashMap::new();
+        for (package, depends) in system
+            .depends
+            .iter()
+            .filter(|(package, _)| system.is_installed(package))
+        {
+            for dependency in depends.iter().filter(|p| system.is_installed(p)) {
+                required_by
+                    .entry(dependency.clone())
+                    .or_default()
+                    .push(package.clone());
+            }
+        }
+        Ok(required_by)
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let system = self.load()?;
+        let reason = if system.explicit.iter().any(|p| p == package_name) {
+            "Explicitly installed"
+        } else if system.deps.iter().any(|p| p == package_name) {
+            "Installed as a dependency for another package"
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("Package {} is not installed", package_name),
+            )
+            .into());
+        };
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", package_name);
+        println!("Install Reason  : {}", reason);
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.record("install", packages, |system, package| {
+            system.forget(package);
+            system.explicit.push(package.clone());
+        })
+    }
+
+    fn install_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.record("install_as_deps", packages, |system, package| {
+            system.forget(package);
+            system.deps.push(package.clone());
+        })
+    }
+
+    fn mark_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.record("mark_as_deps", packages, |system, package| {
+            system.forget(package);
+            system.deps.push(package.clone());
+        })
+    }
+
+    fn mark_packages_as_explicit(&self, packages: &[String]) -> Result<()> {
+        self.record("mark_as_explicit", packages, |system, package| {
+            system.forget(package);
+            system.explicit.push(package.clone());
+        })
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.record("remove", packages, |system, package| system.forget(package))
+    }
+}
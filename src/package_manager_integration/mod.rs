@@ -0,0 +1,413 @@
+//! The package managers moree works with, behind the [`PackageManager`] trait.
+
+mod apt;
+mod cargo;
+mod dnf;
+mod fake;
+mod npm;
+mod pacman;
+mod pacman_db;
+mod pipx;
+mod uv;
+
+use crate::error::{Error, Result};
+use crate::moree_state_manager;
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// Setting this environment variable to `1` makes the `fake` backend available. It
+/// records operations instead of running them and only exists for the test suite.
+pub const FAKE_BACKEND_VAR: &str = "MOREE_FAKE_BACKEND";
+/// The backend used when neither `--backend` nor the config selects one.
+pub const DEFAULT_BACKEND: &str = "paru";
+/// The names `--backend` and `get_backends` accept.
+pub const AVAILABLE_BACKENDS: &[&str] = &[
+    "paru",
+    "yay",
+    "pikaur",
+    "pacman",
+    "pacman-db",
+    "apt",
+    "dnf",
+    "cargo",
+    "pipx",
+    "uv",
+    "npm",
+];
+
+/// Where a backend finds the system it manages. By default, the running system.
+#[derive(Debug, Default, Clone)]
+pub struct BackendOptions {
+    /// An alternative installation root, as with pacman's `--root`.
+    pub root: Option<PathBuf>,
+    /// An alternative package database, as with pacman's `--dbpath`.
+    pub db_path: Option<PathBuf>,
+}
+
+/// A package manager, as far as moree needs it: which packages are installed and why,
+/// and the operations that change that. Package names are bare, without namespace.
+///
+/// Package managers without install reasons, such as cargo or pipx, only implement the
+/// required methods; every package then counts as explicitly installed, and moree
+/// refuses to record any of them as a dependency.
+pub trait PackageManager {
+    /// The backend name, as given to `--backend`.
+    fn name(&self) -> &str;
+    /// State entries are keyed as `namespace:package`. Backends that manage the same
+    /// packages, such as paru and pacman, share a namespace.
+    fn namespace(&self) -> &str;
+
+    /// The packages installed explicitly.
+    fn list_explicit(&self) -> Result<Vec<String>>;
+    /// Prints what the package manager knows about an installed package, for the
+    /// interactive prompts.
+    fn display_package_details(&self, package_name: &str) -> Result<()>;
+    /// Installs packages explicitly.
+    fn install_packages(&self, packages: &[String]) -> Result<()>;
+    /// Removes packages.
+    fn remove_packages(&self, packages: &[String]) -> Result<()>;
+
+    /// Whether the package manager records why packages were installed, so they can
+    /// be installed or marked as dependencies. False by default.
+    fn supports_install_reasons(&self) -> bool {
+        false
+    }
+
+    /// The packages installed as dependencies. None by default.
+    fn list_deps(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Installs packages as dependencies. Unsupported by default.
+    fn install_packages_as_deps(&self, _packages: &[String]) -> Result<()> {
+        Err(unsupported(
+            self.name(),
+            "installing packages as dependencies",
+        ))
+    }
+
+    /// Changes the install reason of installed packages to dependency. Unsupported by
+    /// default.
+    fn mark_packages_as_deps(&self, _packages: &[String]) -> Result<()> {
+        Err(unsupported(self.name(), "marking packages as dependencies"))
+    }
+
+    /// Changes the install reason of installed packages to explicit. Unsupported by
+    /// default.
+    fn mark_packages_as_explicit(&self, _packages: &[String]) -> Result<()> {
+        Err(unsupported(self.name(), "marking packages as explicit"))
+    }
+
+    /// The groups each installed package belongs to. Backends without package groups
+    /// have none.
+    fn package_groups(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(HashMap::new())
+    }
+
+    /// The installed packages that depend on each installed package. Backends whose
+    /// packages do not depend on each other report none.
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(HashMap::new())
+    }
+
+    /// Packages installed as dependencies that no installed package requires any more.
+    /// By default these are the dependencies missing from `required_by`; backends whose
+    /// packages can also be kept by optional or recommended dependencies override this.
+    fn list_orphans(&self) -> Result<Vec<String>> {
+        let required_by = self.required_by()?;
+        Ok(self
+            .list_deps()?
+            .into_iter()
+            .filter(|package| required_by.get(package).is_none_or(Vec::is_empty))
+            .collect())
+    }
+
+    /// `list_explicit` as a set.
+    fn get_installed_packages_hashset(&self) -> Result<HashSet<String>> {
+        Ok(self.list_explicit()?.into_iter().collect())
+    }
+
+    /// `list_deps` as a set.
+    fn get_installed_packages_asdeps_hashset(&self) -> Result<HashSet<String>> {
+        Ok(self.list_deps()?.into_iter().collect())
+    }
+}
+
+/// A package name together with the backend that installed it.
+pub type InstalledPackage<'a> = (&'a dyn PackageManager, String);
+
+/// The selected backends, in the order they were selected. No two of them share a
+/// namespace.
+pub struct Backends {
+    backends: Vec<Box<dyn PackageManager>>,
+}
+
+impl Backends {
+    /// Every selected backend.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn PackageManager> {
+        self.backends.iter().map(|backend| backend.as_ref())
+    }
+
+    /// The namespaces of the selected backends.
+    pub fn namespaces(&self) -> Vec<&str> {
+        self.iter().map(|backend| backend.namespace()).collect()
+    }
+
+    /// The selected backend for `namespace`, if any.
+    pub fn get(&self, namespace: &str) -> Option<&dyn PackageManager> {
+        self.iter().find(|backend| backend.namespace() == namespace)
+    }
+
+    /// With a single backend, names are shown bare so that output stays usable as a
+    /// plain package list.
+    pub fn label(&self, namespace: &str, package_name: &str) -> String {
+        if self.backends.len() == 1 {
+            package_name.to_string()
+        } else {
+            moree_state_manager::qualified_name(namespace, package_name)
+        }
+    }
+
+    /// `label` for a state key such as `cargo:ripgrep`.
+    pub fn label_key(&self, key: &str) -> String {
+        let (namespace, package_name) = moree_state_manager::split_qualified_name(key);
+        self.label(namespace, package_name)
+    }
+
+    /// Finds the backend that has each package installed. Packages that no backend has
+    /// installed are returned separately.
+    pub fn find_installed(
+        &self,
+        packages: &[String],
+    ) -> Result<(Vec<InstalledPackage<'_>>, Vec<String>)> {
+        let index = self.installed_index()?;
+        let mut found = Vec::new();
+        let mut missing = Vec::new();
+        for package in packages {
+            match index.locate(package)? {
+                Some(installed) => found.push(installed),
+                None => missing.push(package.clone()),
+            }
+        }
+
+        Ok((found, missing))
+    }
+
+    /// Queries every backend once for what it has installed.
+    pub fn installed_index(&self) -> Result<InstalledIndex<'_>> {
+        let mut installed = Vec::new();
+        for backend in self.iter() {
+            let mut packages = backend.get_installed_packages_hashset()?;
+            packages.extend(backend.get_installed_packages_asdeps_hashset()?);
+            installed.push((backend, packages));
+        }
+
+        Ok(InstalledIndex {
+            backends: self,
+            installed,
+        })
+    }
+}
+
+/// The installed packages of every selected backend, for looking up many packages
+/// without querying the backends again.
+pub struct InstalledIndex<'a> {
+    backends: &'a Backends,
+    installed: Vec<(&'a dyn PackageManager, HashSet<String>)>,
+}
+
+impl<'a> InstalledIndex<'a> {
+    /// Packages may be qualified with a namespace; bare names have to be unambiguous.
+    pub fn locate(&self, package: &str) -> Result<Option<InstalledPackage<'a>>> {
+        let mut candidates: Vec<_> =
+            match package.split_once(moree_state_manager::NAMESPACE_SEPARATOR) {
+                Some((namespace, package_name)) => {
+                    if self.backends.get(namespace).is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("No selected backend manages the '{}' namespace", namespace),
+                        )
+                        .into());
+                    }
+                    self.installed
+                        .iter()
+                        .filter(|(backend, packages)| {
+                            backend.namespace() == namespace && packages.contains(package_name)
+                        })
+                        .map(|(backend, _)| (*backend, package_name.to_string()))
+                        .collect()
+                }
+                None => self
+                    .installed
+                    .iter()
+                    .filter(|(_, packages)| packages.contains(package))
+                    .map(|(backend, _)| (*backend, package.to_string()))
+                    .collect(),
+            };
+
+        if candidates.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is installed by several backends; use one of: {}",
+                    package,
+                    candidates
+                        .iter()
+                        .map(|(backend, package_name)| {
+                            moree_state_manager::qualified_name(backend.namespace(), package_name)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )
+            .into());
+        }
+        Ok(candidates.pop())
+    }
+}
+
+/// Selects the named backends, all with the same options. `root` and `db_path` point
+/// at a single system, so they are refused when more than one backend is selected;
+/// use `get_backends_with_options` to give each backend its own.
+pub fn get_backends(names: &[String], options: &BackendOptions) -> Result<Backends> {
+    if names.len() > 1 && (options.root.is_some() || options.db_path.is_some()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "A root or database path can only be shared by a single backend",
+        )
+        .into());
+    }
+    let default_names = [DEFAULT_BACKEND.to_string()];
+    let names = if names.is_empty() {
+        &default_names[..]
+    } else {
+        names
+    };
+    let selection: Vec<_> = names
+        .iter()
+        .map(|name| (name.clone(), options.clone()))
+        .collect();
+    get_backends_with_options(&selection)
+}
+
+/// Selects each named backend with its own options. Without any, the default backend
+/// is selected.
+pub fn get_backends_with_options(selection: &[(String, BackendOptions)]) -> Result<Backends> {
+    let mut backends: Vec<Box<dyn PackageManager>> = Vec::new();
+    for (name, options) in selection {
+        let backend = get_backend(name, options)?;
+        if let Some(other) = backends
+            .iter()
+            .find(|other| other.namespace() == backend.namespace())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Backends {} and {} both manage the '{}' namespace; select only one of them",
+                    other.name(),
+                    backend.name(),
+                    backend.namespace()
+                ),
+            )
+            .into());
+        }
+        backends.push(backend);
+    }
+
+    if backends.is_empty() {
+        backends.push(get_backend(DEFAULT_BACKEND, &BackendOptions::default())?);
+    }
+    Ok(Backends { backends })
+}
+
+/// Returns the value in `cell`, loading it with `load` on first use. Backends cache
+/// what they read from the system this way, since commands query it several times.
+pub(crate) fn cached<T>(cell: &OnceCell<T>, load: impl FnOnce() -> Result<T>) -> Result<&T> {
+    if let Some(value) = cell.get() {
+        return Ok(value);
+    }
+    let value = load()?;
+    Ok(cell.get_or_init(|| value))
+}
+
+/// Runs a command that talks to the user directly, such as an install that asks for
+/// confirmation.
+pub(crate) fn run_interactive(command: &mut Command) -> Result<()> {
+    let status = command.status().map_err(|e| spawn_error(command, e))?;
+    if !status.success() {
+        return Err(Error::backend_failed(command, status, &[]));
+    }
+    Ok(())
+}
+
+/// Runs a query and returns what it printed.
+pub(crate) fn run_query(command: &mut Command) -> Result<String> {
+    let output = command_output(command)?;
+    if !output.status.success() {
+        return Err(Error::backend_failed(
+            command,
+            output.status,
+            &output.stderr,
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Runs a command and captures its output, whatever its exit status.
+pub(crate) fn command_output(command: &mut Command) -> Result<Output> {
+    command.output().map_err(|e| spawn_error(command, e))
+}
+
+// Without the program name, a missing package manager only shows up as "No such file
+// or directory".
+fn spawn_error(command: &Command, e: io::Error) -> Error {
+    Error::Io(io::Error::new(
+        e.kind(),
+        format!(
+            "Could not run {}: {}",
+            command.get_program().to_string_lossy(),
+            e
+        ),
+    ))
+}
+
+/// The error for an operation a backend cannot carry out.
+pub(crate) fn unsupported(backend: &str, operation: &str) -> Error {
+    Error::Io(io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("The {} backend does not support {}", backend, operation),
+    ))
+}
+
+/// Whether `get_backend` accepts `name`: one of [`AVAILABLE_BACKENDS`], or the fake
+/// backend the test suite uses when [`FAKE_BACKEND_VAR`] is set to `1`.
+pub fn is_backend(name: &str) -> bool {
+    AVAILABLE_BACKENDS.contains(&name) || (name == "fake" && fake::enabled())
+}
+
+/// Creates the backend called `name`, one of [`AVAILABLE_BACKENDS`].
+pub fn get_backend(name: &str, options: &BackendOptions) -> Result<Box<dyn PackageManager>> {
+    match name {
+        "paru" | "yay" | "pikaur" | "pacman" => Ok(Box::new(pacman::Pacman::new(name, options))),
+        "pacman-db" => Ok(Box::new(pacman_db::PacmanDb::new(options))),
+        "apt" => Ok(Box::new(apt::Apt::new(options))),
+        "dnf" => Ok(Box::new(dnf::Dnf::new(options))),
+        "cargo" => Ok(Box::new(cargo::Cargo::new(options)?)),
+        "pipx" => Ok(Box::new(pipx::Pipx::new())),
+        "uv" => Ok(Box::new(uv::Uv::new())),
+        "npm" => Ok(Box::new(npm::Npm::new(options))),
+        "fake" if fake::enabled() => Ok(Box::new(fake::Fake::new(options)?)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Unknown backend '{}'. Available backends: {}",
+                name,
+                AVAILABLE_BACKENDS.join(", ")
+            ),
+        )
+        .into()),
+    }
+}
@@ -0,0 +1,128 @@
+use super::{cached, run_interactive, run_query, BackendOptions, PackageManager};
+use crate::error::Result;
+use colored::*;
+use serde::Deserialize;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io;
+use std::process::Command;
+
+#[derive(Deserialize)]
+struct NpmList {
+    #[serde(default)]
+    dependencies: BTreeMap<String, NpmPackage>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct NpmPackage {
+    #[serde(default)]
+    pub version: String,
+}
+
+// Globally installed npm packages. Only top-level packages are listed, since their
+// own dependencies are private to each of them.
+pub struct Npm {
+    prefix_args: Vec<OsString>,
+    packages: OnceCell<BTreeMap<String, NpmPackage>>,
+}
+
+impl Npm {
+    pub fn new(options: &BackendOptions) -> Self {
+        let mut prefix_args = Vec::new();
+        if let Some(root) = &options.root {
+            prefix_args.push(OsString::from("--prefix"));
+            prefix_args.push(root.clone().into_os_string());
+        }
+
+        Self {
+            prefix_args,
+            packages: OnceCell::new(),
+        }
+    }
+
+    fn packages(&self) -> Result<&BTreeMap<String, NpmPackage>> {
+        cached(&self.packages, || {
+            let output = run_query(
+                Command::new("npm")
+                    .args(["ls", "--global", "--depth=0", "--json"])
+                    .args(&self.prefix_args),
+            )?;
+            parse_npm_list(&output)
+        })
+    }
+
+    fn run_command(&self, subcommand: &str, packages: &[String]) -> Result<()> {
+        run_interactive(
+            Command::new("npm")
+                .args([subcommand, "--global"])
+                .args(&self.prefix_args)
+                .args(packages),
+        )
+    }
+}
+
+impl PackageManager for Npm {
+    fn name(&self) -> &str {
+        "npm"
+    }
+
+    fn namespace(&self) -> &str {
+        "npm"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.packages()?.keys().cloned().collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let package = self.packages()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not installed globally with npm", package_name),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", package_name);
+        println!("Version         : {}", package.version);
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("install", packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("uninstall", packages)
+    }
+}
+
+fn parse_npm_list(output: &str) -> Result<BTreeMap<String, NpmPackage>> {
+    let list: NpmList = serde_json::from_str(output).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse npm ls output: {}", e),
+        )
+    })?;
+    Ok(list.dependencies)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_global_list() {
+        let packages =
+            parse_npm_list(include_str!("../../tests/fixtures/npm/ls-global.json")).unwrap();
+
+        assert_eq!(
+            packages.keys().collect::<Vec<_>>(),
+            ["@angular/cli", "npm", "typescript"]
+        );
+        assert_eq!(packages["typescript"].version, "5.4.5");
+    }
+}
@@ -0,0 +1,225 @@
+use super::{command_output, run_interactive, run_query, BackendOptions, PackageManager};
+use crate::error::{Error, Result};
+use colored::*;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::process::Command;
+
+// Covers pacman itself and the AUR helpers that share its command-line interface.
+pub struct Pacman {
+    program: String,
+    path_args: Vec<OsString>,
+}
+
+impl Pacman {
+    pub fn new(program: &str, options: &BackendOptions) -> Self {
+        let mut path_args = Vec::new();
+        if let Some(root) = &options.root {
+            path_args.push(OsString::from("--root"));
+            path_args.push(root.clone().into_os_string());
+        }
+        if let Some(db_path) = &options.db_path {
+            path_args.push(OsString::from("--dbpath"));
+            path_args.push(db_path.clone().into_os_string());
+        }
+
+        Self {
+            program: program.to_string(),
+            path_args,
+        }
+    }
+
+    fn query(&self, flag: &str) -> Result<Vec<String>> {
+        let output = run_query(Command::new(&self.program).args(&self.path_args).arg(flag))?;
+
+        let packages: Vec<String> = output.lines().map(String::from).collect();
+
+        Ok(packages)
+    }
+
+    pub fn run_command(&self, flags: &[&str], packages: &[String]) -> Result<()> {
+        // AUR helpers escalate privileges on their own; plain pacman has to be wrapped.
+        let mut command = if self.program == "pacman" {
+            let mut command = Command::new("sudo");
+            command.arg(&self.program);
+            command
+        } else {
+            Command::new(&self.program)
+        };
+
+        run_interactive(command.args(&self.path_args).args(flags).args(packages))
+    }
+}
+
+impl PackageManager for Pacman {
+    fn name(&self) -> &str {
+        &self.program
+    }
+
+    fn namespace(&self) -> &str {
+        "pacman"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        self.query("-Qeq")
+    }
+
+    fn supports_install_reasons(&self) -> bool {
+        true
+    }
+
+    fn list_deps(&self) -> Result<Vec<String>> {
+        self.query("-Qdq")
+    }
+
+    fn package_groups(&self) -> Result<HashMap<String, Vec<String>>> {
+        // `-Qg` prints one `group package` line per group membership.
+        let mut groups: HashMap<String, Vec<String>> = HashMap::new();
+        for line in self.query("-Qg")? {
+            if let Some((group, package)) = line.split_once(' ') {
+                groups
+                    .entry(package.to_string())
+                    .or_default()
+                    .push(group.to_string());
+            }
+        }
+        Ok(groups)
+    }
+
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        // Field names are translated, so the output is requested in English.
+        let output = run_query(
+            Command::new(&self.program)
+                .env("LC_ALL", "C")
+                .args(&self.path_args)
+                .arg("-Qi"),
+        )?;
+        Ok(parse_required_by(&output))
+    }
+
+    fn list_orphans(&self) -> Result<Vec<String>> {
+        // A single `-t` also keeps packages that are only optionally required. pacman
+        // exits with 1 when nothing matches, which here just means there are no orphans.
+        let mut command = Command::new(&self.program);
+        command.args(&self.path_args).arg("-Qdtq");
+        let output = command_output(&mut command)?;
+        let no_matches =
+            output.status.code() == Some(1) && output.stdout.is_empty() && output.stderr.is_empty();
+        if !output.status.success() && !no_matches {
+            return Err(Error::backend_failed(
+                &command,
+                output.status,
+                &output.stderr,
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let output = run_query(
+            Command::new(&self.program)
+                .args(&self.path_args)
+                .arg("-Qi")
+                .arg(package_name),
+        )?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("{}", output);
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["-S"], packages)
+    }
+
+    fn install_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["-S", "--asdeps"], packages)
+    }
+
+    fn mark_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["-D", "--asdeps"], packages)
+    }
+
+    fn mark_packages_as_explicit(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["-D", "--asexplicit"], packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command(&["-R"], packages)
+    }
+}
+
+// Reads the `Required By` field of every package in `pacman -Qi` output. Long lists
+// continue on indented lines.
+fn parse_required_by(output: &str) -> HashMap<String, Vec<String>> {
+    let mut required_by = HashMap::new();
+    let mut name = None;
+    let mut field = "";
+
+    for line in output.lines() {
+        let value = if line.starts_with(' ') {
+            line.trim()
+        } else if let Some((key, value)) = line.split_once(" : ") {
+            field = key.trim();
+            value.trim()
+        } else {
+            field = "";
+            continue;
+        };
+
+        match field {
+            "Name" => name = Some(value.to_string()),
+            "Required By" if value != "None" => {
+                if let Some(name) = &name {
+                    required_by
+                        .entry(name.clone())
+                        .or_insert_with(Vec::new)
+                        .extend(value.split_whitespace().map(String::from));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    required_by
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_required_by_from_package_info() {
+        let output = "\
+Name            : glibc
+Version         : 2.40-1
+Required By     : firefox  gtk3  libnotify  util-linux  zsh  bash  coreutils
+                  systemd
+Optional For    : None
+
+Name            : zsh
+Required By     : None
+";
+
+        let required_by = parse_required_by(output);
+
+        assert_eq!(required_by.len(), 1);
+        assert_eq!(
+            required_by["glibc"],
+            [
+                "firefox",
+                "gtk3",
+                "libnotify",
+                "util-linux",
+                "zsh",
+                "bash",
+                "coreutils",
+                "systemd"
+            ]
+        );
+    }
+}
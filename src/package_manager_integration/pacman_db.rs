@@ -0,0 +1,328 @@
+use super::pacman::Pacman;
+use super::{cached, BackendOptions, PackageManager};
+use crate::error::Result;
+use chrono::{Local, TimeZone};
+use colored::*;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DEFAULT_DB_PATH: &str = "var/lib/pacman";
+
+#[derive(Debug, Clone, Default)]
+pub struct LocalPackage {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub explicit: bool,
+    pub install_date: Option<i64>,
+    pub size: Option<u64>,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+    pub groups: Vec<String>,
+    pub required_by: Vec<String>,
+    pub optional_for: Vec<String>,
+}
+
+// Reads pacman's local database directly instead of spawning a process per query.
+// Modifications still go through pacman itself.
+pub struct PacmanDb {
+    db_path: PathBuf,
+    packages: OnceCell<BTreeMap<String, LocalPackage>>,
+    writer: Pacman,
+}
+
+impl PacmanDb {
+    pub fn new(options: &BackendOptions) -> Self {
+        let db_path = match (&options.db_path, &options.root) {
+            (Some(db_path), _) => db_path.clone(),
+            (None, Some(root)) => root.join(DEFAULT_DB_PATH),
+            (None, None) => Path::new("/").join(DEFAULT_DB_PATH),
+        };
+
+        Self {
+            db_path,
+            packages: OnceCell::new(),
+            writer: Pacman::new("pacman", options),
+        }
+    }
+
+    fn packages(&self) -> Result<&BTreeMap<String, LocalPackage>> {
+        cached(&self.packages, || load_local_database(&self.db_path))
+    }
+
+    fn list_by_reason(&self, explicit: bool) -> Result<Vec<String>> {
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| package.explicit == explicit)
+            .map(|package| package.name.clone())
+            .collect())
+    }
+}
+
+impl PackageManager for PacmanDb {
+    fn name(&self) -> &str {
+        "pacman-db"
+    }
+
+    fn namespace(&self) -> &str {
+        "pacman"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        self.list_by_reason(true)
+    }
+
+    fn supports_install_reasons(&self) -> bool {
+        true
+    }
+
+    fn list_deps(&self) -> Result<Vec<String>> {
+        self.list_by_reason(false)
+    }
+
+    fn package_groups(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| !package.groups.is_empty())
+            .map(|package| (package.name.clone(), package.groups.clone()))
+            .collect())
+    }
+
+    fn required_by(&self) -> Result<HashMap<String, Vec<String>>> {
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| !package.required_by.is_empty())
+            .map(|package| (package.name.clone(), package.required_by.clone()))
+            .collect())
+    }
+
+    fn list_orphans(&self) -> Result<Vec<String>> {
+        // Like `pacman -Qdt`, packages that are only optionally required are kept.
+        Ok(self
+            .packages()?
+            .values()
+            .filter(|package| {
+                !package.explicit
+                    && package.required_by.is_empty()
+                    && package.optional_for.is_empty()
+            })
+            .map(|package| package.name.clone())
+            .collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let package = self.packages()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "Package {} was not found in the local database",
+                    package_name
+                ),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", package.name);
+        println!("Version         : {}", package.version);
+        println!("Description     : {}", package.description);
+        println!("Depends On      : {}", join_or_none(&package.depends));
+        println!("Required By     : {}", join_or_none(&package.required_by));
+        println!("Optional For    : {}", join_or_none(&package.optional_for));
+        println!(
+            "Installed Size  : {}",
+            package.size.map_or("None".to_string(), format_size)
+        );
+        println!(
+            "Install Date    : {}",
+            package
+                .install_date
+                .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single())
+                .map_or("None".to_string(), |date| date.format("%c").to_string())
+        );
+        println!(
+            "Install Reason  : {}",
+            if package.explicit {
+                "Explicitly installed"
+            } else {
+                "Installed as a dependency for another package"
+            }
+        );
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.writer.install_packages(packages)
+    }
+
+    fn install_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.writer.install_packages_as_deps(packages)
+    }
+
+    fn mark_packages_as_deps(&self, packages: &[String]) -> Result<()> {
+        self.writer.mark_packages_as_deps(packages)
+    }
+
+    fn mark_packages_as_explicit(&self, packages: &[String]) -> Result<()> {
+        self.writer.mark_packages_as_explicit(packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.writer.remove_packages(packages)
+    }
+}
+
+pub fn load_local_database(db_path: &Path) -> Result<BTreeMap<String, LocalPackage>> {
+    let local_dir = db_path.join("local");
+    let entries = fs::read_dir(&local_dir).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Could not read {}: {}", local_dir.display(), e),
+        )
+    })?;
+
+    let mut packages = BTreeMap::new();
+    for entry in entries {
+        let desc_path = entry?.path().join("desc");
+        // The local directory also holds ALPM_DB_VERSION, which has no desc file.
+        if !desc_path.is_file() {
+            continue;
+        }
+
+        let package = parse_desc(&fs::read_to_string(&desc_path)?);
+        if package.name.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} has no %NAME% entry", desc_path.display()),
+            )
+            .into());
+        }
+        packages.insert(package.name.clone(), package);
+    }
+
+    resolve_required_by(&mut packages);
+    Ok(packages)
+}
+
+fn parse_desc(content: &str) -> LocalPackage {
+    let mut package = LocalPackage {
+        explicit: true, // A missing %REASON% means explicitly installed.
+        ..Default::default()
+    };
+    let mut section = "";
+
+    for line in content.lines() {
+        if line.starts_with('%') && line.ends_with('%') && line.len() > 1 {
+            section = line;
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        match section {
+            "%NAME%" => package.name = line.to_string(),
+            "%VERSION%" => package.version = line.to_string(),
+            "%DESC%" => package.description = line.to_string(),
+            "%INSTALLDATE%" => package.install_date = line.parse().ok(),
+            "%SIZE%" => package.size = line.parse().ok(),
+            "%REASON%" => package.explicit = line == "0",
+            "%DEPENDS%" => package.depends.push(line.to_string()),
+            // Optional dependencies are followed by a description, as in `name: reason`.
+            "%OPTDEPENDS%" => package.optdepends.push(line.to_string()),
+            "%PROVIDES%" => package.provides.push(line.to_string()),
+            "%GROUPS%" => package.groups.push(line.to_string()),
+            _ => {}
+        }
+    }
+
+    package
+}
+
+// Fills in which installed packages depend, or optionally depend, on each package,
+// directly or through something it provides.
+fn resolve_required_by(packages: &mut BTreeMap<String, LocalPackage>) {
+    let mut providers: HashMap<String, Vec<String>> = HashMap::new();
+    for package in packages.values() {
+        providers
+            .entry(package.name.clone())
+            .or_default()
+            .push(package.name.clone());
+        for provided in &package.provides {
+            providers
+                .entry(strip_version_constraint(provided).to_string())
+                .or_default()
+                .push(package.name.clone());
+        }
+    }
+
+    let required_by = reverse_relations(packages, &providers, |package| &package.depends);
+    let optional_for = reverse_relations(packages, &providers, |package| &package.optdepends);
+    for package in packages.values_mut() {
+        package.required_by = required_by.get(&package.name).cloned().unwrap_or_default();
+        package.optional_for = optional_for.get(&package.name).cloned().unwrap_or_default();
+    }
+}
+
+// Maps each provider to the sorted packages that list it in `relations`.
+fn reverse_relations(
+    packages: &BTreeMap<String, LocalPackage>,
+    providers: &HashMap<String, Vec<String>>,
+    relations: impl Fn(&LocalPackage) -> &[String],
+) -> HashMap<String, Vec<String>> {
+    let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+    for package in packages.values() {
+        for relation in relations(package) {
+            let Some(found) = providers.get(strip_version_constraint(relation)) else {
+                continue;
+            };
+            for provider in found {
+                dependents
+                    .entry(provider.clone())
+                    .or_default()
+                    .push(package.name.clone());
+            }
+        }
+    }
+
+    for names in dependents.values_mut() {
+        names.sort();
+        names.dedup();
+    }
+    dependents
+}
+
+fn strip_version_constraint(dependency: &str) -> &str {
+    dependency
+        .split(['<', '>', '=', ':'])
+        .next()
+        .unwrap_or(dependency)
+        .trim()
+}
+
+fn join_or_none(items: &[String]) -> String {
+    if items.is_empty() {
+        "None".to_string()
+    } else {
+        items.join("  ")
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", size, units[unit])
+}
@@ -0,0 +1,156 @@
+use super::{cached, run_interactive, run_query, PackageManager};
+use crate::error::Result;
+use colored::*;
+use serde::Deserialize;
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::process::Command;
+
+#[derive(Deserialize)]
+struct PipxList {
+    venvs: HashMap<String, PipxVenv>,
+}
+
+#[derive(Deserialize)]
+struct PipxVenv {
+    metadata: PipxMetadata,
+}
+
+#[derive(Deserialize)]
+struct PipxMetadata {
+    main_package: PipxPackage,
+    #[serde(default)]
+    injected_packages: HashMap<String, PipxPackage>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PipxPackage {
+    pub package: String,
+    pub package_version: String,
+    #[serde(default)]
+    pub apps: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PipxEnvironment {
+    pub main_package: PipxPackage,
+    pub injected: Vec<String>,
+}
+
+// Applications installed with pipx, one virtual environment each.
+pub struct Pipx {
+    environments: OnceCell<BTreeMap<String, PipxEnvironment>>,
+}
+
+impl Pipx {
+    pub fn new() -> Self {
+        Self {
+            environments: OnceCell::new(),
+        }
+    }
+
+    fn environments(&self) -> Result<&BTreeMap<String, PipxEnvironment>> {
+        cached(&self.environments, || {
+            let output = run_query(Command::new("pipx").args(["list", "--json"]))?;
+            parse_pipx_list(&output)
+        })
+    }
+
+    fn run_command(&self, subcommand: &str, packages: &[String]) -> Result<()> {
+        // pipx uninstall takes a single package, so install and uninstall both run once
+        // per package.
+        for package in packages {
+            run_interactive(Command::new("pipx").arg(subcommand).arg(package))?;
+        }
+        Ok(())
+    }
+}
+
+impl PackageManager for Pipx {
+    fn name(&self) -> &str {
+        "pipx"
+    }
+
+    fn namespace(&self) -> &str {
+        "pipx"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.environments()?.keys().cloned().collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let environment = self.environments()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not installed with pipx", package_name),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", environment.main_package.package);
+        println!(
+            "Version         : {}",
+            environment.main_package.package_version
+        );
+        println!(
+            "Apps            : {}",
+            environment.main_package.apps.join("  ")
+        );
+        if !environment.injected.is_empty() {
+            println!("Injected        : {}", environment.injected.join("  "));
+        }
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("install", packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("uninstall", packages)
+    }
+}
+
+fn parse_pipx_list(output: &str) -> Result<BTreeMap<String, PipxEnvironment>> {
+    let list: PipxList = serde_json::from_str(output).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse pipx list output: {}", e),
+        )
+    })?;
+
+    Ok(list
+        .venvs
+        .into_iter()
+        .map(|(name, venv)| {
+            let mut injected: Vec<String> = venv.metadata.injected_packages.into_keys().collect();
+            injected.sort();
+            (
+                name,
+                PipxEnvironment {
+                    main_package: venv.metadata.main_package,
+                    injected,
+                },
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_pipx_list() {
+        let environments =
+            parse_pipx_list(include_str!("../../tests/fixtures/pipx/list.json")).unwrap();
+
+        assert_eq!(environments.keys().collect::<Vec<_>>(), ["black", "poetry"]);
+        assert_eq!(environments["black"].main_package.package_version, "24.4.2");
+        assert_eq!(environments["poetry"].injected, ["poetry-plugin-export"]);
+    }
+}
@@ -0,0 +1,130 @@
+use super::{cached, run_interactive, run_query, PackageManager};
+use crate::error::Result;
+use colored::*;
+use std::cell::OnceCell;
+use std::collections::BTreeMap;
+use std::io;
+use std::process::Command;
+
+#[derive(Debug, Clone, Default)]
+pub struct UvTool {
+    pub name: String,
+    pub version: String,
+    pub executables: Vec<String>,
+}
+
+// Tools installed with `uv tool install`.
+pub struct Uv {
+    tools: OnceCell<BTreeMap<String, UvTool>>,
+}
+
+impl Uv {
+    pub fn new() -> Self {
+        Self {
+            tools: OnceCell::new(),
+        }
+    }
+
+    fn tools(&self) -> Result<&BTreeMap<String, UvTool>> {
+        cached(&self.tools, || {
+            let output = run_query(Command::new("uv").args(["tool", "list"]))?;
+            Ok(parse_tool_list(&output))
+        })
+    }
+
+    fn run_command(&self, subcommand: &str, packages: &[String]) -> Result<()> {
+        // uv tool install takes a single package, so install and uninstall both run once
+        // per package.
+        for package in packages {
+            run_interactive(Command::new("uv").args(["tool", subcommand]).arg(package))?;
+        }
+        Ok(())
+    }
+}
+
+impl PackageManager for Uv {
+    fn name(&self) -> &str {
+        "uv"
+    }
+
+    fn namespace(&self) -> &str {
+        "uv"
+    }
+
+    fn list_explicit(&self) -> Result<Vec<String>> {
+        Ok(self.tools()?.keys().cloned().collect())
+    }
+
+    fn display_package_details(&self, package_name: &str) -> Result<()> {
+        let tool = self.tools()?.get(package_name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} is not installed with uv tool", package_name),
+            )
+        })?;
+
+        println!("{}", "\nPackage Details:".bold().yellow());
+        println!("Name            : {}", tool.name);
+        println!("Version         : {}", tool.version);
+        println!("Executables     : {}", tool.executables.join("  "));
+        println!();
+
+        Ok(())
+    }
+
+    fn install_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("install", packages)
+    }
+
+    fn remove_packages(&self, packages: &[String]) -> Result<()> {
+        self.run_command("uninstall", packages)
+    }
+}
+
+// `uv tool list` prints `name vX.Y.Z` per tool, followed by `- executable` lines.
+fn parse_tool_list(output: &str) -> BTreeMap<String, UvTool> {
+    let mut tools = BTreeMap::new();
+    let mut current: Option<UvTool> = None;
+
+    for line in output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(executable) = line.strip_prefix("- ") {
+            if let Some(tool) = current.as_mut() {
+                tool.executables.push(executable.to_string());
+            }
+            continue;
+        }
+
+        if let Some(tool) = current.take() {
+            tools.insert(tool.name.clone(), tool);
+        }
+        let mut parts = line.split_whitespace();
+        current = Some(UvTool {
+            name: parts.next().unwrap_or_default().to_string(),
+            version: parts
+                .next()
+                .unwrap_or_default()
+                .trim_start_matches('v')
+                .to_string(),
+            executables: Vec::new(),
+        });
+    }
+    if let Some(tool) = current.take() {
+        tools.insert(tool.name.clone(), tool);
+    }
+
+    tools
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_recorded_tool_list() {
+        let tools = parse_tool_list(include_str!("../../tests/fixtures/uv/tool-list.txt"));
+
+        assert_eq!(tools.keys().collect::<Vec<_>>(), ["black", "ruff"]);
+        assert_eq!(tools["black"].version, "24.4.2");
+        assert_eq!(tools["black"].executables, ["black", "blackd"]);
+    }
+}
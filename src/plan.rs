@@ -0,0 +1,461 @@
+//! Plans: the batches of package operations `moree apply` runs to bring the system in
+//! line with the state.
+
+use crate::error::Result;
+use crate::moree_state_manager::{self, PackageState};
+use crate::package_manager_integration::{Backends, PackageManager};
+use crate::protected::Protected;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const PLAN_VERSION: u32 = 1;
+
+/// The actions in the order they are carried out. Reasons are fixed first, so that
+/// nothing that is about to be removed or installed depends on a stale mark.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Mark installed dependencies as explicitly installed.
+    MarkExplicit,
+    /// Mark explicitly installed packages as dependencies.
+    MarkDep,
+    /// Install packages explicitly.
+    Install,
+    /// Install packages as dependencies.
+    InstallAsDep,
+    /// Remove explicitly installed packages.
+    Remove,
+    /// Remove packages installed as dependencies, such as orphans.
+    RemoveDep,
+}
+
+impl Action {
+    /// Every action, in the order plans carry them out.
+    pub const ALL: [Action; 6] = [
+        Action::MarkExplicit,
+        Action::MarkDep,
+        Action::Install,
+        Action::InstallAsDep,
+        Action::Remove,
+        Action::RemoveDep,
+    ];
+
+    /// A short description for headings, such as "Install as dependency".
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::MarkExplicit => "Mark as explicitly installed",
+            Action::MarkDep => "Mark as dependency",
+            Action::Install => "Install",
+            Action::InstallAsDep => "Install as dependency",
+            Action::Remove => "Remove",
+            Action::RemoveDep => "Remove dependency",
+        }
+    }
+
+    fn run(self, backend: &dyn PackageManager, packages: &[String]) -> Result<()> {
+        match self {
+            Action::MarkExplicit => backend.mark_packages_as_explicit(packages),
+            Action::MarkDep => backend.mark_packages_as_deps(packages),
+            Action::Install => backend.install_packages(packages),
+            Action::InstallAsDep => backend.install_packages_as_deps(packages),
+            Action::Remove | Action::RemoveDep => backend.remove_packages(packages),
+        }
+    }
+
+    /// The action that undoes this one. Packages are reinstalled the way they were
+    /// installed before their removal.
+    pub fn inverse(self) -> Action {
+        match self {
+            Action::MarkExplicit => Action::MarkDep,
+            Action::MarkDep => Action::MarkExplicit,
+            Action::Install => Action::Remove,
+            Action::InstallAsDep => Action::RemoveDep,
+            Action::Remove => Action::Install,
+            Action::RemoveDep => Action::InstallAsDep,
+        }
+    }
+
+    /// Whether the action removes packages.
+    pub fn is_removal(self) -> bool {
+        matches!(self, Action::Remove | Action::RemoveDep)
+    }
+
+    // Whether the action makes sense for a package in the given state: not installed
+    // (`None`), or installed explicitly (`Some(true)`) or as a dependency.
+    fn applies_to(self, explicit: Option<bool>) -> bool {
+        match self {
+            Action::MarkExplicit => explicit == Some(false),
+            Action::MarkDep => explicit == Some(true),
+            Action::Install | Action::InstallAsDep => explicit.is_none(),
+            Action::Remove | Action::RemoveDep => explicit.is_some(),
+        }
+    }
+}
+
+/// One batch of packages handed to a single backend operation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The namespace of the backend that runs the step.
+    pub namespace: String,
+    /// What is done to the packages.
+    pub action: Action,
+    /// Bare package names, sorted.
+    pub packages: Vec<String>,
+}
+
+/// How a step of a run went.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "result", rename_all = "kebab-case")]
+pub enum Outcome {
+    /// The backend carried out the step.
+    Succeeded,
+    /// The backend reported an error.
+    Failed {
+        /// The error, as shown to the user.
+        error: String,
+    },
+    /// Not attempted because an earlier batch for the same backend failed.
+    Skipped,
+}
+
+/// A step together with how it went, as recorded in the journal.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StepResult {
+    /// The step that ran.
+    #[serde(flatten)]
+    pub step: Step,
+    /// How it went.
+    #[serde(flatten)]
+    pub outcome: Outcome,
+}
+
+/// The steps of one run, in the order they are carried out.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Plan {
+    /// The steps, ordered by action.
+    pub steps: Vec<Step>,
+}
+
+// The on-disk form written by `apply --plan-out`.
+#[derive(Serialize, Deserialize)]
+struct PlanDocument {
+    version: u32,
+    #[serde(flatten)]
+    plan: Plan,
+}
+
+impl Plan {
+    /// Computes the steps that bring every backend in line with `package_states`.
+    /// Install reasons are always fixed; `with_install` adds installs of managed packages
+    /// that are missing, and `with_uninstall` removals of unmanaged explicit packages.
+    pub fn build(
+        package_states: &HashMap<String, PackageState>,
+        backends: &Backends,
+        with_install: bool,
+        with_uninstall: bool,
+    ) -> Result<Self> {
+        let mut steps = Vec::new();
+        for backend in backends.iter() {
+            let package_states =
+                moree_state_manager::package_states_in(package_states, backend.namespace());
+            let mut batches = plan_backend(&package_states, backend, with_install, with_uninstall)?;
+            for action in Action::ALL {
+                let mut packages = batches.remove(&action).unwrap_or_default();
+                if !packages.is_empty() {
+                    packages.sort();
+                    steps.push(Step {
+                        namespace: backend.namespace().to_string(),
+                        action,
+                        packages,
+                    });
+                }
+            }
+        }
+        steps.sort_by_key(|step| step.action);
+
+        Ok(Self { steps })
+    }
+
+    /// Drops protected packages from the removals and returns them.
+    pub fn take_protected(
+        &mut self,
+        backends: &Backends,
+        protected: &Protected,
+    ) -> Result<Vec<String>> {
+        let mut taken = Vec::new();
+        for step in self.steps.iter_mut() {
+            if !step.action.is_removal() {
+                continue;
+            }
+            let groups = backend_for(backends, step)?.package_groups()?;
+            step.packages.retain(|package| {
+                let package_groups = groups.get(package).map_or(&[][..], Vec::as_slice);
+                if protected.is_protected(&step.namespace, package, package_groups) {
+                    taken.push(backends.label(&step.namespace, package));
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.steps.retain(|step| !step.packages.is_empty());
+
+        Ok(taken)
+    }
+
+    /// Whether the plan has nothing to do.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Prints the plan grouped by action, with package names labelled as `backends`
+    /// labels them.
+    pub fn print(&self, backends: &Backends) {
+        for action in Action::ALL {
+            let packages: Vec<_> = self
+                .steps
+                .iter()
+                .filter(|step| step.action == action)
+                .flat_map(|step| {
+                    step.packages
+                        .iter()
+                        .map(|package| backends.label(&step.namespace, package))
+                })
+                .collect();
+            if packages.is_empty() {
+                continue;
+            }
+
+            let header = format!("{} ({}):", action.description(), packages.len());
+            println!(
+                "{}",
+                match action {
+                    Action::Install | Action::InstallAsDep => header.green(),
+                    Action::Remove | Action::RemoveDep => header.red(),
+                    Action::MarkExplicit | Action::MarkDep => header.yellow(),
+                }
+                .bold()
+            );
+            for package in packages {
+                println!("  {}", package);
+            }
+        }
+    }
+
+    /// Builds the plan that undoes the succeeded steps of an earlier run, last step first.
+    pub fn inverse_of(results: &[StepResult]) -> Self {
+        let steps = results
+            .iter()
+            .rev()
+            .filter(|result| result.outcome == Outcome::Succeeded)
+            .map(|result| Step {
+                namespace: result.step.namespace.clone(),
+                action: result.step.action.inverse(),
+                packages: result.step.packages.clone(),
+            })
+            .collect();
+
+        Self { steps }
+    }
+
+    /// The steps of an earlier run that failed or were skipped, to be retried.
+    pub fn unfinished_of(results: &[StepResult]) -> Self {
+        let steps = results
+            .iter()
+            .filter(|result| result.outcome != Outcome::Succeeded)
+            .map(|result| result.step.clone())
+            .collect();
+
+        Self { steps }
+    }
+
+    /// Runs every step and keeps going after failures, since batches touch disjoint
+    /// packages. Removals are the exception: once anything failed for a backend, its
+    /// packages are left in place rather than removed from a half-updated system.
+    pub fn execute(&self, backends: &Backends) -> Vec<StepResult> {
+        let mut failed_namespaces = HashSet::new();
+        let mut results = Vec::new();
+        for step in &self.steps {
+            let outcome = if step.action.is_removal() && failed_namespaces.contains(&step.namespace)
+            {
+                Outcome::Skipped
+            } else {
+                match backend_for(backends, step)
+                    .and_then(|backend| step.action.run(backend, &step.packages))
+                {
+                    Ok(()) => Outcome::Succeeded,
+                    Err(err) => {
+                        failed_namespaces.insert(step.namespace.clone());
+                        Outcome::Failed {
+                            error: err.to_string(),
+                        }
+                    }
+                }
+            };
+            results.push(StepResult {
+                step: step.clone(),
+                outcome,
+            });
+        }
+
+        results
+    }
+
+    /// Verifies that every package is still installed the way the plan assumed when it
+    /// was computed, so a stale plan is refused instead of partly applied.
+    pub fn check(&self, backends: &Backends) -> Result<()> {
+        let mut mismatches = Vec::new();
+        let mut installed: HashMap<&str, (HashSet<String>, HashSet<String>)> = HashMap::new();
+
+        for step in &self.steps {
+            let backend = backend_for(backends, step)?;
+            if !installed.contains_key(step.namespace.as_str()) {
+                installed.insert(
+                    &step.namespace,
+                    (
+                        backend.get_installed_packages_hashset()?,
+                        backend.get_installed_packages_asdeps_hashset()?,
+                    ),
+                );
+            }
+            let (explicit, deps) = &installed[step.namespace.as_str()];
+
+            for package in &step.packages {
+                let actual = if explicit.contains(package) {
+                    Some(true)
+                } else if deps.contains(package) {
+                    Some(false)
+                } else {
+                    None
+                };
+                if !step.action.applies_to(actual) {
+                    mismatches.push(format!(
+                        "{} ({}): {}",
+                        backends.label(&step.namespace, package),
+                        step.action.description().to_lowercase(),
+                        describe_reason(actual)
+                    ));
+                }
+            }
+        }
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::other(format!(
+                "The system no longer matches the plan:\n  {}",
+                mismatches.join("\n  ")
+            ))
+            .into())
+        }
+    }
+}
+
+/// Writes `plan` to `file_path` as JSON, for `apply --plan-in` to run later.
+pub fn save_plan(plan: Plan, file_path: &Path) -> Result<()> {
+    let document = PlanDocument {
+        version: PLAN_VERSION,
+        plan,
+    };
+    let data = serde_json::to_string_pretty(&document).map_err(io::Error::from)?;
+    Ok(fs::write(file_path, data)?)
+}
+
+/// Reads a plan written by `save_plan`. The plan is not checked against the system;
+/// see [`Plan::check`].
+pub fn load_plan(file_path: &Path) -> Result<Plan> {
+    let data = fs::read_to_string(file_path).map_err(|e| {
+        io::Error::new(
+            e.kind(),
+            format!("Could not read plan file {}: {}", file_path.display(), e),
+        )
+    })?;
+    let document: PlanDocument = serde_json::from_str(&data).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Could not parse plan file {}: {}", file_path.display(), e),
+        )
+    })?;
+
+    if document.version != PLAN_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Plan file {} has version {}, but this moree only understands version {}",
+                file_path.display(),
+                document.version,
+                PLAN_VERSION
+            ),
+        )
+        .into());
+    }
+    Ok(document.plan)
+}
+
+fn backend_for<'a>(backends: &'a Backends, step: &Step) -> Result<&'a dyn PackageManager> {
+    backends.get(&step.namespace).ok_or_else(|| {
+        io::Error::other(format!(
+            "No selected backend manages the '{}' namespace",
+            step.namespace
+        ))
+        .into()
+    })
+}
+
+fn describe_reason(explicit: Option<bool>) -> &'static str {
+    match explicit {
+        Some(true) => "now installed explicitly",
+        Some(false) => "now installed as a dependency",
+        None => "no longer installed",
+    }
+}
+
+fn plan_backend(
+    package_states: &HashMap<String, PackageState>,
+    backend: &dyn PackageManager,
+    with_install: bool,
+    with_uninstall: bool,
+) -> Result<HashMap<Action, Vec<String>>> {
+    let installed_packages = backend.get_installed_packages_hashset()?;
+    let installed_asdeps_packages = backend.get_installed_packages_asdeps_hashset()?;
+    let mut batches: HashMap<Action, Vec<String>> = HashMap::new();
+
+    for (package, state) in package_states {
+        let is_installed =
+            installed_packages.contains(package) || installed_asdeps_packages.contains(package);
+        // Without install reasons, every package is installed explicitly.
+        let explicit = state.explicit || !backend.supports_install_reasons();
+        let action = if explicit {
+            if installed_asdeps_packages.contains(package) {
+                Some(Action::MarkExplicit)
+            } else if with_install && !is_installed {
+                Some(Action::Install)
+            } else {
+                None
+            }
+        } else if installed_packages.contains(package) {
+            Some(Action::MarkDep)
+        } else if with_install && !is_installed {
+            Some(Action::InstallAsDep)
+        } else {
+            None
+        };
+
+        if let Some(action) = action {
+            batches.entry(action).or_default().push(package.clone());
+        }
+    }
+
+    if with_uninstall {
+        let to_remove = installed_packages
+            .into_iter()
+            .filter(|package| !package_states.contains_key(package));
+        batches.entry(Action::Remove).or_default().extend(to_remove);
+    }
+
+    Ok(batches)
+}
@@ -0,0 +1,121 @@
+//! Packages that must never be removed, whatever the state says.
+
+use crate::moree_state_manager::NAMESPACE_SEPARATOR;
+
+/// Packages whose removal would leave a system unbootable or without a way to
+/// install anything again. Patterns from the config file are added to these.
+pub const DEFAULT_PROTECTED: &[&str] = &[
+    "base",
+    "linux",
+    "linux-lts",
+    "linux-zen",
+    "linux-hardened",
+    "linux-rt",
+    "linux-firmware",
+    "kernel",
+    "kernel-core",
+    "linux-image-*",
+    "systemd",
+    "grub",
+    "efibootmgr",
+    "sudo",
+    "pacman",
+    "paru*",
+    "yay*",
+    "pikaur*",
+    "apt",
+    "dpkg",
+    "dnf",
+    "rpm",
+    "npm:npm",
+    "npm:corepack",
+];
+
+/// Patterns are globs matched against package names and the groups a package belongs
+/// to. A pattern qualified with a namespace, such as `pacman:linux*`, only applies to
+/// that namespace.
+pub struct Protected {
+    patterns: Vec<String>,
+}
+
+impl Protected {
+    /// The built-in patterns together with `patterns`, usually those from the config.
+    pub fn new(patterns: &[String]) -> Self {
+        Self {
+            patterns: DEFAULT_PROTECTED
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .chain(patterns.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// Whether a package of `namespace`, belonging to `groups`, matches any pattern.
+    pub fn is_protected(&self, namespace: &str, package: &str, groups: &[String]) -> bool {
+        self.patterns.iter().any(|pattern| {
+            let pattern = match pattern.split_once(NAMESPACE_SEPARATOR) {
+                Some((pattern_namespace, pattern)) if pattern_namespace == namespace => pattern,
+                Some(_) => return false,
+                None => pattern,
+            };
+            glob_match(pattern, package) || groups.iter().any(|group| glob_match(pattern, group))
+        })
+    }
+}
+
+// Supports `*` for any run of characters and `?` for a single one.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was seen, and how much of the name it has swallowed so far.
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, swallowed)) = backtrack {
+            p = star + 1;
+            n = swallowed + 1;
+            backtrack = Some((star, n));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_globs() {
+        assert!(glob_match("linux", "linux"));
+        assert!(!glob_match("linux", "linux-lts"));
+        assert!(glob_match("linux*", "linux-lts"));
+        assert!(glob_match("*-firmware", "linux-firmware"));
+        assert!(glob_match("lib?", "libc"));
+        assert!(!glob_match("lib?", "lib"));
+        assert!(glob_match("py*-*-git", "python-foo-bar-git"));
+        assert!(!glob_match("py*-*-git", "python-foo"));
+    }
+
+    #[test]
+    fn matches_groups_and_namespaces() {
+        let protected = Protected::new(&["pacman:base-devel".to_string(), "ripgrep".to_string()]);
+
+        assert!(protected.is_protected("pacman", "linux", &[]));
+        assert!(protected.is_protected("pacman", "make", &["base-devel".to_string()]));
+        assert!(!protected.is_protected("apt", "make", &["base-devel".to_string()]));
+        assert!(protected.is_protected("cargo", "ripgrep", &[]));
+        assert!(!protected.is_protected("pacman", "firefox", &[]));
+        assert!(protected.is_protected("npm", "npm", &[]));
+        assert!(!protected.is_protected("pacman", "npm", &[]));
+    }
+}
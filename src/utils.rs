@@ -1,10 +1,52 @@
-use dialoguer::{theme::ColorfulTheme, Confirm};
-use std::io;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use std::io::{self, IsTerminal};
+
+// Prompts read from stdin and draw on stderr; both have to be a terminal.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+// Fails with `hint` when there is no terminal to prompt on, e.g. under cron or in a pipe.
+pub fn require_terminal(hint: &str) -> io::Result<()> {
+    if is_interactive() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("Not running in an interactive terminal; {}", hint),
+        ))
+    }
+}
 
 pub fn confirm_prompt(message: &str, default: bool) -> io::Result<bool> {
+    require_terminal("cannot ask for confirmation")?;
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt(message)
         .default(default)
         .interact()
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+        .map_err(prompt_error)
+}
+
+pub fn select_prompt(message: &str, items: &[&str], default: usize) -> io::Result<usize> {
+    require_terminal("cannot ask for a choice")?;
+    Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(message)
+        .items(items)
+        .default(default)
+        .interact()
+        .map_err(prompt_error)
+}
+
+pub fn input_prompt(message: &str, initial_text: &str) -> io::Result<String> {
+    require_terminal("cannot ask for input")?;
+    Input::new()
+        .with_prompt(message)
+        .with_initial_text(initial_text)
+        .allow_empty(true)
+        .interact_text()
+        .map_err(prompt_error)
+}
+
+fn prompt_error(e: dialoguer::Error) -> io::Error {
+    io::Error::other(e.to_string())
 }
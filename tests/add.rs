@@ -0,0 +1,234 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+#[test]
+fn rejects_packages_that_are_not_installed() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    let output = sandbox.run(&["add", "firefox", "chromium"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("chromium"));
+    assert!(!sandbox.has_state());
+}
+
+#[test]
+fn skips_packages_already_managed_as_explicit() {
+    let sandbox = Sandbox::new(&["firefox", "git"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": "browser" },
+        "fake:git": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["add"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(sandbox.read_state()["fake:firefox"]["memo"], "browser");
+    assert_eq!(sandbox.read_state()["fake:git"]["explicit"], true);
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn records_explicit_packages_with_a_memo_without_prompting() {
+    let sandbox = Sandbox::new(&["openconnect", "firefox"], &[]);
+
+    let output = sandbox.run(&[
+        "add",
+        "openconnect",
+        "firefox",
+        "--explicit",
+        "--memo",
+        "needed for work VPN",
+    ]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "fake:openconnect": { "explicit": true, "memo": "needed for work VPN" },
+            "fake:firefox": { "explicit": true, "memo": "needed for work VPN" },
+        })
+    );
+    assert!(stdout(&output).contains("openconnect recorded as explicitly installed"));
+}
+
+#[test]
+fn records_dependencies_and_keeps_existing_memos() {
+    let sandbox = Sandbox::new(&["firefox"], &["gtk3"]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": "browser" },
+    }));
+
+    let output = sandbox.run(&["add", "firefox", "gtk3", "--dep"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "fake:firefox": { "explicit": false, "memo": "browser" },
+            "fake:gtk3": { "explicit": false, "memo": null },
+        })
+    );
+}
+
+#[test]
+fn adds_tags_to_existing_ones() {
+    let sandbox = Sandbox::new(&["steam", "slack"], &[]);
+    sandbox.write_state(json!({
+        "fake:steam": { "explicit": true, "memo": null, "tags": ["games"] },
+    }));
+
+    let output = sandbox.run(&[
+        "add",
+        "steam",
+        "slack",
+        "--explicit",
+        "--tag",
+        "work, chat",
+        "--tag",
+        "chat",
+    ]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "fake:steam": { "explicit": true, "memo": null, "tags": ["chat", "games", "work"] },
+            "fake:slack": { "explicit": true, "memo": null, "tags": ["chat", "work"] },
+        })
+    );
+}
+
+#[test]
+fn flags_still_reject_packages_that_are_not_installed() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    let output = sandbox.run(&["add", "firefox", "chromium", "--explicit"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("chromium"));
+    assert!(!sandbox.has_state());
+}
+
+#[test]
+fn reason_flags_need_packages_and_exclude_each_other() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    assert!(!sandbox.run(&["add", "--explicit"]).status.success());
+    assert!(!sandbox
+        .run(&["add", "firefox", "--explicit", "--dep"])
+        .status
+        .success());
+    assert!(!sandbox
+        .run(&["add", "firefox", "--memo", "browser"])
+        .status
+        .success());
+    assert!(!sandbox.has_state());
+}
+
+#[test]
+fn records_decisions_from_a_file_and_reports_rejected_lines() {
+    let sandbox = Sandbox::new(&["firefox", "openconnect"], &["gtk3"]);
+    let decisions = sandbox.dir().join("decisions.txt");
+    std::fs::write(
+        &decisions,
+        "# packages from the old list\n\
+         firefox explicit \"browser\"\n\
+         gtk3 dep\n\
+         chromium explicit\n\
+         openconnect maybe\n\
+         \n\
+         firefox dep\n",
+    )
+    .unwrap();
+
+    let output = sandbox.run(&["add", "--from", decisions.to_str().unwrap()]);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("line 4: chromium is not installed"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("line 5: unknown reason 'maybe'"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("line 7: firefox is already listed on line 2"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("3 lines were rejected"), "{}", stderr);
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "fake:firefox": { "explicit": true, "memo": "browser" },
+            "fake:gtk3": { "explicit": false, "memo": null },
+        })
+    );
+}
+
+#[test]
+fn records_decisions_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let sandbox = Sandbox::new(&["firefox"], &["gtk3"]);
+    let mut child = sandbox
+        .command()
+        .args(["add", "--from", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"firefox explicit\ngtk3 dep\n")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Recorded 2 packages.\n");
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "fake:firefox": { "explicit": true, "memo": null },
+            "fake:gtk3": { "explicit": false, "memo": null },
+        })
+    );
+}
+
+#[test]
+fn stamps_entries_and_keeps_when_they_were_added() {
+    let sandbox = Sandbox::new(&["firefox", "git"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": {
+            "explicit": true,
+            "memo": "browser",
+            "added_at": "2023-04-01T10:00:00Z",
+            "modified_at": "2023-04-01T10:00:00Z",
+            "reviewed_at": "2023-04-01T10:00:00Z",
+        },
+    }));
+
+    let output = sandbox.run(&["add", "firefox", "git", "--explicit"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let state = sandbox.read_state();
+    let firefox = &state["fake:firefox"];
+    assert_eq!(firefox["added_at"], "2023-04-01T10:00:00Z");
+    assert_eq!(firefox["modified_at"], "2023-04-01T10:00:00Z");
+    assert!(firefox["reviewed_at"].as_str().unwrap() > "2024");
+    let git = &state["fake:git"];
+    assert!(git["added_at"].is_string());
+    assert_eq!(git["added_at"], git["modified_at"]);
+    assert_eq!(git["added_at"], git["reviewed_at"]);
+}
@@ -0,0 +1,346 @@
+mod common;
+
+use common::{stderr, stdout, strings, Sandbox};
+use serde_json::json;
+
+#[test]
+fn marks_packages_recorded_as_dependencies() {
+    let sandbox = Sandbox::new(&["firefox", "gtk3"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![("mark_as_deps".to_string(), strings(&["gtk3"]))]
+    );
+}
+
+#[test]
+fn with_install_installs_missing_explicit_packages() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--with-install", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![("install".to_string(), strings(&["neovim"]))]
+    );
+}
+
+#[test]
+fn with_uninstall_removes_unmanaged_packages() {
+    let sandbox = Sandbox::new(&["firefox", "chromium"], &["gtk3"]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--with-uninstall", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![("remove".to_string(), strings(&["chromium"]))]
+    );
+}
+
+#[test]
+fn sync_installs_removes_and_marks() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--sync", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![
+            ("mark_as_deps".to_string(), strings(&["gtk3"])),
+            ("install".to_string(), strings(&["neovim"])),
+            ("remove".to_string(), strings(&["chromium"])),
+        ]
+    );
+}
+
+#[test]
+fn fixes_install_reasons_in_both_directions_in_one_batch_each() {
+    let sandbox = Sandbox::new(&["firefox", "gtk3", "glib2"], &["neovim", "vim"]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "fake:glib2": { "explicit": false, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:vim": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--with-install", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![
+            ("mark_as_explicit".to_string(), strings(&["neovim", "vim"])),
+            ("mark_as_deps".to_string(), strings(&["glib2", "gtk3"])),
+        ]
+    );
+}
+
+#[test]
+fn with_install_installs_missing_dependencies_as_dependencies() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--with-install", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![("install_as_deps".to_string(), strings(&["gtk3"]))]
+    );
+    assert_eq!(sandbox.system()["deps"], json!(["gtk3"]));
+}
+
+#[test]
+fn dry_run_prints_the_plan_grouped_by_action_without_changing_anything() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "fake:glib2": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--sync", "--dry-run"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "Mark as explicitly installed (1):\n  vim\n\
+         Mark as dependency (1):\n  gtk3\n\
+         Install (1):\n  neovim\n\
+         Install as dependency (1):\n  glib2\n\
+         Remove (1):\n  chromium\n"
+    );
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn reports_when_there_is_nothing_to_do() {
+    let sandbox = Sandbox::new(&["firefox"], &["gtk3"]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply", "--sync"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Nothing to do.\n");
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn plan_out_writes_the_plan_without_changing_anything() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    let plan_path = sandbox.dir().join("plan.json");
+
+    let output = sandbox.run(&["apply", "--sync", "--plan-out", plan_path.to_str().unwrap()]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(sandbox.operations().is_empty());
+    let plan: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&plan_path).unwrap()).unwrap();
+    assert_eq!(
+        plan,
+        json!({
+            "version": 1,
+            "steps": [
+                { "namespace": "fake", "action": "mark-dep", "packages": ["gtk3"] },
+                { "namespace": "fake", "action": "install", "packages": ["neovim"] },
+                { "namespace": "fake", "action": "remove", "packages": ["chromium"] },
+            ],
+        })
+    );
+}
+
+#[test]
+fn plan_in_runs_exactly_the_planned_operations() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    let plan_path = sandbox.dir().join("plan.json");
+    let plan_path = plan_path.to_str().unwrap();
+    let output = sandbox.run(&["apply", "--sync", "--plan-out", plan_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Later changes to the state do not affect a written plan.
+    sandbox.write_state(json!({}));
+    let output = sandbox.run(&["apply", "--plan-in", plan_path, "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        vec![
+            ("mark_as_deps".to_string(), strings(&["gtk3"])),
+            ("install".to_string(), strings(&["neovim"])),
+            ("remove".to_string(), strings(&["chromium"])),
+        ]
+    );
+}
+
+#[test]
+fn plan_in_refuses_a_plan_the_system_no_longer_matches() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    let plan_path = sandbox.dir().join("plan.json");
+    let plan_path = plan_path.to_str().unwrap();
+    let output = sandbox.run(&["apply", "--sync", "--plan-out", plan_path]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let later = Sandbox::new(&["gtk3", "neovim"], &[]);
+    let output = later.run(&["apply", "--plan-in", plan_path, "--yes"]);
+
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("no longer matches the plan"), "{}", stderr);
+    assert!(
+        stderr.contains("neovim (install): now installed explicitly"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("chromium (remove): no longer installed"),
+        "{}",
+        stderr
+    );
+    assert!(later.operations().is_empty());
+}
+
+#[test]
+fn never_removes_protected_packages() {
+    let sandbox = Sandbox::new(&["base", "linux-lts", "paru-bin", "make", "chromium"], &[]);
+    sandbox.set_groups(json!({ "make": ["base-devel"] }));
+    sandbox.write_config(json!({ "protected": ["base-devel"] }));
+    sandbox.write_state(json!({}));
+
+    let output = sandbox.run(&["apply", "--with-uninstall", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output)
+        .contains("Not removing protected packages: base, linux-lts, make, paru-bin"));
+    assert_eq!(
+        sandbox.operations(),
+        vec![("remove".to_string(), strings(&["chromium"]))]
+    );
+}
+
+#[test]
+fn plan_in_refuses_a_plan_that_removes_protected_packages() {
+    let sandbox = Sandbox::new(&["linux", "chromium"], &[]);
+    let plan_path = sandbox.dir().join("plan.json");
+    std::fs::write(
+        &plan_path,
+        json!({
+            "version": 1,
+            "steps": [
+                { "namespace": "fake", "action": "remove", "packages": ["chromium", "linux"] },
+            ],
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    let output = sandbox.run(&["apply", "--plan-in", plan_path.to_str().unwrap(), "--yes"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The plan removes protected packages: linux"));
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn keeps_going_after_a_failed_batch_and_skips_removals_for_that_backend() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    sandbox.set_failing(&["neovim"]);
+
+    let output = sandbox.run(&["apply", "--sync", "--yes"]);
+
+    assert!(!output.status.success());
+    assert_eq!(
+        sandbox.operations(),
+        vec![
+            ("mark_as_explicit".to_string(), strings(&["vim"])),
+            ("mark_as_deps".to_string(), strings(&["gtk3"])),
+        ]
+    );
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("succeeded  Mark as dependency: gtk3"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("failed     Install: neovim (fake install neovim failed with exit code 1: neovim is set to fail)"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("skipped    Remove: chromium"), "{}", stdout);
+    assert!(stderr(&output).contains("2 of 4 batches did not complete"));
+}
+
+#[test]
+fn resume_retries_only_what_did_not_complete() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    sandbox.set_failing(&["neovim"]);
+    let output = sandbox.run(&["apply", "--sync", "--yes"]);
+    assert!(!output.status.success());
+
+    sandbox.set_failing(&[]);
+    let output = sandbox.run(&["apply", "--resume", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations()[2..],
+        [
+            ("install".to_string(), strings(&["neovim"])),
+            ("remove".to_string(), strings(&["chromium"])),
+        ]
+    );
+
+    let output = sandbox.run(&["apply", "--resume", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Nothing to resume: journal entry 2 (resume 1) completed."));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{fixture_path, stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn run(sandbox: &Sandbox, args: &[&str]) -> std::process::Output {
+    sandbox
+        .bare_command()
+        .arg("--backend")
+        .arg("apt")
+        .arg("--root")
+        .arg(fixture_path("debian"))
+        .args(args)
+        .output()
+        .expect("run moree")
+}
+
+#[test]
+fn manual_packages_are_explicit() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "apt:vim": { "explicit": true, "memo": null },
+    }));
+
+    let output = run(&sandbox, &["unmanaged"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "curl\n");
+}
+
+#[test]
+fn auto_installed_packages_are_dependencies() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "apt:libcurl4": { "explicit": true, "memo": null },
+        "apt:curl": { "explicit": false, "memo": null },
+        "apt:nano": { "explicit": true, "memo": null },
+    }));
+
+    let output = run(&sandbox, &["diff", "--all"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains("+ libcurl4 [explicitly]"));
+    assert!(stdout.contains("- libcurl4 [non-explicitly]"));
+    assert!(stdout.contains("+ curl [non-explicitly]"));
+    assert!(stdout.contains("- curl [explicitly]"));
+    assert!(stdout.contains("+ nano [explicitly managed, but not installed]"));
+}
+
+#[test]
+fn why_follows_depends_to_manual_packages() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = run(&sandbox, &["why", "libc6"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains("  curl -> libc6\n"), "{}", stdout);
+    assert!(stdout.contains("  vim -> libc6\n"), "{}", stdout);
+    assert!(!stdout.contains("libcurl4 ->"), "{}", stdout);
+}
+
+#[test]
+fn recommended_and_suggested_packages_are_not_orphans() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = run(&sandbox, &["orphans"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Orphaned dependencies (1):\n  libgpm2\n");
+}
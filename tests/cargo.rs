@@ -0,0 +1,33 @@
+mod common;
+
+use common::{fixture_path, stderr, stdout, Sandbox};
+use serde_json::json;
+
+#[test]
+fn lists_crates_installed_with_cargo() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "cargo:ripgrep": { "explicit": true, "memo": "fast grep" },
+        "cargo:bat": { "explicit": true, "memo": null },
+    }));
+
+    let unmanaged = sandbox
+        .bare_command()
+        .args(["--backend", "cargo", "--root"])
+        .arg(fixture_path("cargo"))
+        .arg("unmanaged")
+        .output()
+        .expect("run moree");
+    assert!(unmanaged.status.success(), "{}", stderr(&unmanaged));
+    assert_eq!(stdout(&unmanaged), "cargo-edit\nmoree\n");
+
+    let diff = sandbox
+        .bare_command()
+        .args(["--backend", "cargo", "--root"])
+        .arg(fixture_path("cargo"))
+        .args(["diff", "--all"])
+        .output()
+        .expect("run moree");
+    assert!(diff.status.success(), "{}", stderr(&diff));
+    assert!(stdout(&diff).contains("+ bat [explicitly managed, but not installed]"));
+}
@@ -0,0 +1,180 @@
+#![allow(dead_code)] // Each test binary only uses part of the sandbox helpers.
+
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+pub const STATE_VERSION: u64 = 2;
+
+// An isolated moree environment: its own state file, config directory and a fake
+// package manager whose installed packages and recorded operations live in a JSON file.
+pub struct Sandbox {
+    dir: TempDir,
+}
+
+impl Sandbox {
+    pub fn new(explicit: &[&str], deps: &[&str]) -> Self {
+        let sandbox = Sandbox {
+            dir: TempDir::new().expect("create temp dir"),
+        };
+        let system = json!({ "explicit": explicit, "deps": deps, "operations": [] });
+        fs::write(sandbox.system_path(), system.to_string()).expect("write fake system");
+        sandbox
+    }
+
+    pub fn state_path(&self) -> PathBuf {
+        self.dir.path().join("state.json")
+    }
+
+    pub fn lock_path(&self) -> PathBuf {
+        self.dir.path().join("state.json.lock")
+    }
+
+    pub fn dir(&self) -> &std::path::Path {
+        self.dir.path()
+    }
+
+    pub fn system_path(&self) -> PathBuf {
+        self.dir.path().join("system.json")
+    }
+
+    // Assigns packages of the fake system to groups, keyed by package.
+    pub fn set_groups(&self, groups: Value) {
+        let mut system = self.system();
+        system["groups"] = groups;
+        fs::write(self.system_path(), system.to_string()).expect("write fake system");
+    }
+
+    // Sets the packages each package of the fake system depends on.
+    pub fn set_depends(&self, depends: Value) {
+        let mut system = self.system();
+        system["depends"] = depends;
+        fs::write(self.system_path(), system.to_string()).expect("write fake system");
+    }
+
+    // Makes every operation that touches one of `packages` fail.
+    pub fn set_failing(&self, packages: &[&str]) {
+        let mut system = self.system();
+        system["failing"] = json!(packages);
+        fs::write(self.system_path(), system.to_string()).expect("write fake system");
+    }
+
+    pub fn journal_entry(&self, id: u64) -> Value {
+        let path = self.dir.path().join("journal").join(format!("{}.json", id));
+        let data = fs::read_to_string(path).expect("read journal entry");
+        serde_json::from_str(&data).expect("parse journal entry")
+    }
+
+    pub fn write_config(&self, config: Value) {
+        let config_dir = self.dir.path().join("config").join("moree");
+        fs::create_dir_all(&config_dir).expect("create config dir");
+        fs::write(config_dir.join("config.json"), config.to_string()).expect("write config");
+    }
+
+    // Writes `packages` as a state file in the current schema version.
+    pub fn write_state(&self, packages: Value) {
+        self.write_raw_state(json!({ "version": STATE_VERSION, "packages": packages }));
+    }
+
+    pub fn write_raw_state(&self, document: Value) {
+        fs::write(self.state_path(), document.to_string()).expect("write state file");
+    }
+
+    // The packages of the state file, which has to be in the current schema version.
+    pub fn read_state(&self) -> Value {
+        let document = self.read_raw_state();
+        assert_eq!(document["version"], STATE_VERSION);
+        document["packages"].clone()
+    }
+
+    // The packages of the state file without their timestamps, which change on every run.
+    pub fn read_reasons(&self) -> Value {
+        let mut packages = self.read_state();
+        for state in packages.as_object_mut().expect("packages map").values_mut() {
+            let state = state.as_object_mut().expect("package state");
+            state.retain(|field, _| !field.ends_with("_at"));
+        }
+        packages
+    }
+
+    pub fn read_raw_state(&self) -> Value {
+        let data = fs::read_to_string(self.state_path()).expect("read state file");
+        serde_json::from_str(&data).expect("parse state file")
+    }
+
+    pub fn has_state(&self) -> bool {
+        self.state_path().exists()
+    }
+
+    pub fn system(&self) -> Value {
+        let data = fs::read_to_string(self.system_path()).expect("read fake system");
+        serde_json::from_str(&data).expect("parse fake system")
+    }
+
+    pub fn operations(&self) -> Vec<(String, Vec<String>)> {
+        self.system()["operations"]
+            .as_array()
+            .expect("operations array")
+            .iter()
+            .map(|op| {
+                let mut packages: Vec<String> = op["packages"]
+                    .as_array()
+                    .expect("packages array")
+                    .iter()
+                    .map(|p| p.as_str().unwrap().to_string())
+                    .collect();
+                packages.sort();
+                (op["operation"].as_str().unwrap().to_string(), packages)
+            })
+            .collect()
+    }
+
+    // moree pointed at the sandbox state file, without choosing a backend.
+    pub fn bare_command(&self) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_moree"));
+        command
+            .env("XDG_CONFIG_HOME", self.dir.path().join("config"))
+            .env("XDG_DATA_HOME", self.dir.path().join("data"))
+            .env("NO_COLOR", "1")
+            .env("TZ", "UTC")
+            .env(moree::package_manager_integration::FAKE_BACKEND_VAR, "1")
+            .arg("--data")
+            .arg(self.state_path());
+        command
+    }
+
+    pub fn command(&self) -> Command {
+        let mut command = self.bare_command();
+        command
+            .arg("--backend")
+            .arg("fake")
+            .arg("--dbpath")
+            .arg(self.system_path());
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Output {
+        self.command().args(args).output().expect("run moree")
+    }
+}
+
+pub fn fixture_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+pub fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["neovim"]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "fake:firefox": { "explicit": true, "memo": null },
+    }));
+    sandbox
+}
+
+#[test]
+fn shows_install_reason_mismatches() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["diff"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains("+ neovim [explicitly]"));
+    assert!(stdout.contains("- neovim [non-explicitly]"));
+    assert!(stdout.contains("+ gtk3 [non-explicitly]"));
+    assert!(stdout.contains("- gtk3 [explicitly]"));
+    assert!(!stdout.contains("firefox"));
+    assert!(!stdout.contains("chromium"));
+}
+
+#[test]
+fn all_includes_missing_and_unmanaged_packages() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["diff", "--all"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stdout = stdout(&output);
+    assert!(stdout.contains("+ firefox [explicitly managed, but not installed]"));
+    assert!(stdout.contains("- chromium [unmanaged]"));
+    assert!(sandbox.operations().is_empty());
+}
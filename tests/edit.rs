@@ -0,0 +1,21 @@
+mod common;
+
+use common::{stderr, Sandbox};
+use serde_json::json;
+
+#[test]
+fn refuses_unmanaged_packages() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_state(json!({
+        "fake:git": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["edit", "firefox"]);
+
+    assert_eq!(output.status.code(), Some(7));
+    assert!(stderr(&output).contains("Package firefox is not managed"));
+    assert_eq!(
+        sandbox.read_state(),
+        json!({ "fake:git": { "explicit": true, "memo": null } })
+    );
+}
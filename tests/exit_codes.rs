@@ -0,0 +1,82 @@
+mod common;
+
+use common::{stderr, Sandbox};
+use serde_json::json;
+
+#[test]
+fn malformed_state_files_report_their_position() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    std::fs::write(
+        sandbox.state_path(),
+        "{\n  \"version\": 2,\n  \"packages\": {,\n}",
+    )
+    .unwrap();
+
+    let output = sandbox.run(&["query", "-e"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("at line 3, column 16"), "{}", stderr);
+    assert!(!stderr.contains("column 16 at line"), "{}", stderr);
+}
+
+#[test]
+fn packages_that_are_not_installed_have_their_own_exit_code() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    let output = sandbox.run(&["add", "chromium", "--explicit"]);
+
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("The following packages are not installed: chromium"));
+}
+
+#[test]
+fn partially_applied_runs_have_their_own_exit_code() {
+    let sandbox = Sandbox::new(&["gtk3"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    sandbox.set_failing(&["vim"]);
+
+    let output = sandbox.run(&["apply", "--yes"]);
+
+    assert_eq!(output.status.code(), Some(8));
+    assert!(stderr(&output).contains("1 of 2 batches did not complete"));
+}
+
+#[test]
+fn the_fake_backend_needs_the_test_switch() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    let output = sandbox
+        .command()
+        .env_remove(moree::package_manager_integration::FAKE_BACKEND_VAR)
+        .args(["query", "-e"])
+        .output()
+        .expect("run moree");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("Unknown backend 'fake'"), "{}", stderr);
+    assert!(!stderr.contains("npm, fake"), "{}", stderr);
+}
+
+#[test]
+fn missing_package_managers_are_named() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = sandbox
+        .bare_command()
+        .env("PATH", sandbox.dir())
+        .args(["--backend", "pipx", "unmanaged"])
+        .output()
+        .expect("run moree");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stderr(&output).contains("Could not run pipx: "),
+        "{}",
+        stderr(&output)
+    );
+}
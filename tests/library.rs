@@ -0,0 +1,107 @@
+mod common;
+
+use common::Sandbox;
+use moree::diff::{self, Difference, DifferenceKind};
+use moree::{Action, BackendOptions, PackageState, Plan};
+use serde_json::json;
+
+fn backends(sandbox: &Sandbox) -> moree::Backends {
+    std::env::set_var(moree::package_manager_integration::FAKE_BACKEND_VAR, "1");
+    let options = BackendOptions {
+        root: None,
+        db_path: Some(sandbox.system_path()),
+    };
+    moree::get_backends(&["fake".to_string()], &options).expect("select fake backend")
+}
+
+#[test]
+fn loads_diffs_and_plans_without_the_cli() {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["neovim"]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": "editor" },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    let backends = backends(&sandbox);
+
+    let package_states = moree::load_package_states(&sandbox.state_path()).unwrap();
+    assert_eq!(
+        package_states["fake:neovim"].memo.as_deref(),
+        Some("editor")
+    );
+
+    let differences = diff::diff(&package_states, &backends, true).unwrap();
+    let kinds: Vec<_> = differences
+        .iter()
+        .map(|Difference { package, kind, .. }| (package.as_str(), *kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("neovim", DifferenceKind::ExpectedExplicit),
+            ("gtk3", DifferenceKind::ExpectedDependency),
+            ("chromium", DifferenceKind::Unmanaged),
+        ]
+    );
+
+    let plan = Plan::build(&package_states, &backends, false, false).unwrap();
+    let actions: Vec<_> = plan
+        .steps
+        .iter()
+        .map(|step| (step.action, step.packages.clone()))
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            (Action::MarkExplicit, vec!["neovim".to_string()]),
+            (Action::MarkDep, vec!["gtk3".to_string()]),
+        ]
+    );
+}
+
+#[test]
+fn saves_state_in_the_current_schema() {
+    let sandbox = Sandbox::new(&[], &[]);
+    let package_states = [(
+        "fake:git".to_string(),
+        PackageState {
+            explicit: true,
+            memo: None,
+            tags: vec!["dev".to_string()],
+            ..Default::default()
+        },
+    )]
+    .into_iter()
+    .collect();
+
+    moree::save_package_states(&sandbox.state_path(), &package_states).unwrap();
+
+    assert_eq!(
+        sandbox.read_state(),
+        json!({ "fake:git": { "explicit": true, "memo": null, "tags": ["dev"] } })
+    );
+}
+
+#[test]
+fn malformed_state_files_are_reported_as_typed_errors() {
+    let sandbox = Sandbox::new(&[], &[]);
+    std::fs::write(
+        sandbox.state_path(),
+        "{\n  \"version\": 2,\n  \"packages\": {,\n}",
+    )
+    .unwrap();
+
+    let error = moree::load_package_states(&sandbox.state_path()).unwrap_err();
+
+    assert!(
+        matches!(
+            error,
+            moree::Error::StateParse {
+                line: 3,
+                column: 16,
+                ..
+            }
+        ),
+        "{:?}",
+        error
+    );
+}
@@ -0,0 +1,160 @@
+mod common;
+
+use common::{fixture_path, stderr, stdout, Sandbox};
+use serde_json::json;
+use std::path::Path;
+use std::process::Output;
+
+// The fake backend and the cargo fixture side by side.
+fn run_both(sandbox: &Sandbox, args: &[&str]) -> Output {
+    sandbox
+        .bare_command()
+        .args(["--backend", "fake", "--backend", "cargo", "--dbpath"])
+        .arg(scoped("fake", &sandbox.system_path()))
+        .arg("--root")
+        .arg(scoped("cargo", &fixture_path("cargo")))
+        .args(args)
+        .output()
+        .expect("run moree")
+}
+
+fn scoped(backend: &str, path: &Path) -> String {
+    format!("{}={}", backend, path.display())
+}
+
+#[test]
+fn paths_must_name_their_backend_when_several_are_selected() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = sandbox
+        .bare_command()
+        .args(["--backend", "fake", "--backend", "cargo", "--dbpath"])
+        .arg(sandbox.system_path())
+        .arg("query")
+        .output()
+        .expect("run moree");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("--dbpath would apply to every selected backend"));
+
+    let output = sandbox
+        .bare_command()
+        .args(["--backend", "fake", "--dbpath"])
+        .arg(scoped("cargo", &sandbox.system_path()))
+        .arg("query")
+        .output()
+        .expect("run moree");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("--dbpath names the cargo backend, which is not selected"));
+}
+
+#[test]
+fn legacy_entries_move_into_the_pacman_namespace() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_raw_state(json!({
+        "firefox": { "explicit": true, "memo": "browser" },
+    }));
+
+    let output = sandbox
+        .bare_command()
+        .args(["--backend", "cargo", "--root"])
+        .arg(fixture_path("cargo"))
+        .args(["add", "ripgrep", "--explicit"])
+        .output()
+        .expect("run moree");
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({
+            "pacman:firefox": { "explicit": true, "memo": "browser" },
+            "cargo:ripgrep": { "explicit": true, "memo": null },
+        })
+    );
+}
+
+#[test]
+fn several_backends_show_qualified_names() {
+    let sandbox = Sandbox::new(&["zsh", "ripgrep"], &[]);
+    sandbox.write_state(json!({
+        "fake:ripgrep": { "explicit": true, "memo": null },
+        "cargo:ripgrep": { "explicit": true, "memo": null },
+    }));
+
+    let output = run_both(&sandbox, &["unmanaged"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "cargo:cargo-edit\ncargo:moree\nfake:zsh\n");
+
+    let output = run_both(&sandbox, &["query"]);
+    assert_eq!(stdout(&output), "cargo:ripgrep\nfake:ripgrep\n");
+}
+
+#[test]
+fn backend_flag_filters_to_one_namespace() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "fake:zsh": { "explicit": true, "memo": null },
+        "cargo:ripgrep": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox
+        .bare_command()
+        .args(["--backend", "cargo", "--root"])
+        .arg(fixture_path("cargo"))
+        .arg("query")
+        .output()
+        .expect("run moree");
+
+    assert_eq!(stdout(&output), "ripgrep\n");
+}
+
+#[test]
+fn bare_names_installed_by_several_backends_are_ambiguous() {
+    let sandbox = Sandbox::new(&["ripgrep"], &[]);
+    sandbox.write_state(json!({
+        "cargo:ripgrep": { "explicit": true, "memo": null },
+    }));
+
+    let output = run_both(&sandbox, &["add", "ripgrep"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("fake:ripgrep, cargo:ripgrep"));
+
+    let output = run_both(&sandbox, &["add", "cargo:ripgrep"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn packages_without_install_reasons_are_never_dependencies() {
+    let sandbox = Sandbox::new(&[], &["gtk3"]);
+
+    let output = run_both(&sandbox, &["add", "cargo:ripgrep", "gtk3", "--dep"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains(
+        "cargo:ripgrep cannot be recorded as a dependency; the cargo backend has no install reasons"
+    ));
+    assert!(!sandbox.has_state());
+
+    let decisions = sandbox.dir().join("decisions.txt");
+    std::fs::write(&decisions, "cargo:ripgrep dep\ngtk3 dep\n").unwrap();
+    let output = run_both(&sandbox, &["add", "--from", decisions.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("line 1: cargo:ripgrep cannot be recorded as a dependency"));
+    assert_eq!(
+        sandbox.read_reasons(),
+        json!({ "fake:gtk3": { "explicit": false, "memo": null } })
+    );
+
+    // Entries recorded as dependencies before are taken as explicit.
+    sandbox.write_state(json!({
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "cargo:ripgrep": { "explicit": false, "memo": null },
+    }));
+    let output = run_both(&sandbox, &["diff"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stdout(&output).contains("ripgrep"), "{}", stdout(&output));
+
+    let output = run_both(&sandbox, &["apply", "--dry-run"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Nothing to do.\n");
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&["firefox"], &["gtk3", "libpng"]);
+    sandbox.set_depends(json!({ "firefox": ["gtk3"] }));
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": "browser" },
+        "fake:gtk3": { "explicit": false, "memo": "toolkit" },
+        "fake:libpng": { "explicit": false, "memo": "for gimp" },
+        "fake:oldlib": { "explicit": false, "memo": null },
+    }));
+    sandbox
+}
+
+#[test]
+fn lists_orphans_and_dependency_entries_nothing_requires() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["orphans"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+Orphaned dependencies (1):
+  libpng: for gimp
+Recorded as dependencies, but nothing requires them (2):
+  libpng (orphaned)
+  oldlib (not installed)
+"
+    );
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn removes_orphans_and_forgets_their_entries() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["orphans", "--remove", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations(),
+        [("remove".to_string(), vec!["libpng".to_string()])]
+    );
+    let state = sandbox.read_state();
+    assert!(state.get("fake:libpng").is_none());
+    assert_eq!(state["fake:oldlib"]["explicit"], false);
+    assert_eq!(sandbox.journal_entry(1)["command"], "orphans");
+}
+
+#[test]
+fn rollback_reinstalls_removed_orphans_as_dependencies() {
+    let sandbox = sandbox();
+    let output = sandbox.run(&["orphans", "--remove", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = sandbox.run(&["rollback", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations()[1],
+        ("install_as_deps".to_string(), vec!["libpng".to_string()])
+    );
+}
+
+#[test]
+fn keeps_protected_orphans() {
+    let sandbox = sandbox();
+    sandbox.write_config(json!({ "protected": ["libpng"] }));
+
+    let output = sandbox.run(&["orphans", "--remove", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Not removing protected packages: libpng"));
+    assert!(sandbox.operations().is_empty());
+    assert!(sandbox.read_state().get("fake:libpng").is_some());
+}
+
+#[test]
+fn removal_needs_confirmation() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["orphans", "--remove"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("pass --yes"));
+    assert!(sandbox.operations().is_empty());
+    assert!(sandbox.read_state().get("fake:libpng").is_some());
+}
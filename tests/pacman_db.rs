@@ -0,0 +1,79 @@
+mod common;
+
+use common::{fixture_path, stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn run(sandbox: &Sandbox, args: &[&str]) -> std::process::Output {
+    sandbox
+        .bare_command()
+        .arg("--backend")
+        .arg("pacman-db")
+        .arg("--dbpath")
+        .arg(fixture_path("pacman"))
+        .args(args)
+        .output()
+        .expect("run moree")
+}
+
+#[test]
+fn reads_install_reasons_from_local_database() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "pacman:firefox": { "explicit": true, "memo": null },
+        "pacman:gtk3": { "explicit": true, "memo": null },
+    }));
+
+    let output = run(&sandbox, &["unmanaged"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "zsh\n");
+
+    let output = run(&sandbox, &["diff"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("+ gtk3 [explicitly]"));
+    assert!(stdout(&output).contains("- gtk3 [non-explicitly]"));
+}
+
+#[test]
+fn root_locates_the_database_below_it() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = sandbox
+        .bare_command()
+        .arg("--backend")
+        .arg("pacman-db")
+        .arg("--root")
+        .arg(fixture_path("missing-root"))
+        .arg("unmanaged")
+        .output()
+        .expect("run moree");
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("missing-root/var/lib/pacman/local"));
+}
+
+#[test]
+fn why_resolves_provides_in_the_dependency_chain() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "pacman:firefox": { "explicit": true, "memo": "browser" },
+    }));
+
+    let output = run(&sandbox, &["why", "libnotify"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("  firefox -> libnotify\n    firefox: browser\n"));
+
+    let output = run(&sandbox, &["why", "glibc"]);
+    assert!(stdout(&output).contains("  firefox -> glibc\n"));
+    assert!(stdout(&output).contains("  zsh -> glibc\n"));
+}
+
+#[test]
+fn optional_dependencies_are_not_orphans() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = run(&sandbox, &["orphans"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "Orphaned dependencies (1):\n  lua\n");
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": {
+            "explicit": true,
+            "memo": "editor",
+            "tags": ["dev", "work"],
+            "added_at": "2023-04-01T10:00:00Z",
+            "reviewed_at": "2024-06-15T10:00:00Z",
+        },
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "fake:firefox": {
+            "explicit": true,
+            "memo": null,
+            "tags": ["work"],
+            "reviewed_at": "2025-03-01T10:00:00Z",
+        },
+    }));
+    sandbox
+}
+
+#[test]
+fn lists_all_managed_packages_sorted() {
+    let output = sandbox().run(&["query"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "firefox\ngtk3\nneovim\n");
+}
+
+#[test]
+fn filters_by_install_reason() {
+    let sandbox = sandbox();
+
+    assert_eq!(stdout(&sandbox.run(&["query", "-e"])), "firefox\nneovim\n");
+    assert_eq!(stdout(&sandbox.run(&["query", "-d"])), "gtk3\n");
+}
+
+#[test]
+fn information_shows_reason_and_memo() {
+    let output = sandbox().run(&["query", "-i", "-e"]);
+
+    let stdout = stdout(&output);
+    assert!(stdout.contains("Name            : neovim"));
+    assert!(stdout.contains("Install Reason  : Explicitly installed"));
+    assert!(stdout.contains("Memo            : editor"));
+    assert!(stdout.contains("Tags            : dev, work"));
+    assert!(stdout.contains("Added           : 2023-04-01"));
+    assert!(stdout.contains("Modified        : Unknown"));
+    assert!(stdout.contains("Reviewed        : 2024-06-15"));
+    assert!(!stdout.contains("gtk3"));
+}
+
+#[test]
+fn filters_by_tag() {
+    let sandbox = sandbox();
+
+    assert_eq!(
+        stdout(&sandbox.run(&["query", "--tag", "work"])),
+        "firefox\nneovim\n"
+    );
+    assert_eq!(stdout(&sandbox.run(&["query", "--tag", "dev"])), "neovim\n");
+    assert_eq!(
+        stdout(&sandbox.run(&["query", "-d", "--tag", "work"])),
+        "\n"
+    );
+}
+
+#[test]
+fn filters_by_review_date() {
+    let sandbox = sandbox();
+
+    assert_eq!(
+        stdout(&sandbox.run(&["query", "--reviewed-before", "2025-01-01"])),
+        "gtk3\nneovim\n"
+    );
+    assert_eq!(
+        stdout(&sandbox.run(&["query", "-e", "--reviewed-before", "2024-01-01"])),
+        "\n"
+    );
+    assert!(!sandbox
+        .run(&["query", "--reviewed-before", "last week"])
+        .status
+        .success());
+}
+
+#[test]
+fn explicit_and_deps_conflict() {
+    let output = sandbox().run(&["query", "-e", "-d"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("may not be used together"));
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&["firefox"], &["gtk3"]);
+    sandbox.write_state(json!({
+        "fake:firefox": {
+            "explicit": true,
+            "memo": "browser",
+            "reviewed_at": "2024-06-15T10:00:00Z",
+        },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    sandbox
+}
+
+#[test]
+fn skips_reasons_reviewed_within_the_interval() {
+    let sandbox = sandbox();
+    sandbox.write_config(json!({ "review_interval_days": 36500 }));
+
+    let output = sandbox.run(&["review"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("No reasons are older than 36500 days"));
+}
+
+#[test]
+fn prompts_for_stale_reasons_and_needs_a_terminal() {
+    let sandbox = sandbox();
+    sandbox.write_config(json!({ "review_interval_days": 36500 }));
+    let before = sandbox.read_raw_state();
+
+    let output = sandbox.run(&["review", "--interval", "30"]);
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("use `add <package> --explicit`"));
+    assert_eq!(sandbox.read_raw_state(), before);
+}
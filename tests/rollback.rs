@@ -0,0 +1,132 @@
+mod common;
+
+use common::{stderr, strings, Sandbox};
+use serde_json::json;
+
+fn synced_sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&["gtk3", "chromium"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+    let output = sandbox.run(&["apply", "--sync", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    sandbox
+}
+
+#[test]
+fn apply_records_every_batch_in_the_journal() {
+    let sandbox = synced_sandbox();
+
+    let entry = sandbox.journal_entry(1);
+    assert_eq!(entry["id"], 1);
+    assert_eq!(entry["command"], "apply");
+    assert!(entry["timestamp"].is_string());
+    assert_eq!(
+        entry["steps"],
+        json!([
+            { "namespace": "fake", "action": "mark-explicit", "packages": ["vim"], "result": "succeeded" },
+            { "namespace": "fake", "action": "mark-dep", "packages": ["gtk3"], "result": "succeeded" },
+            { "namespace": "fake", "action": "install", "packages": ["neovim"], "result": "succeeded" },
+            { "namespace": "fake", "action": "remove", "packages": ["chromium"], "result": "succeeded" },
+        ])
+    );
+}
+
+#[test]
+fn records_failed_batches() {
+    let sandbox = Sandbox::new(&["firefox"], &["vim"]);
+    sandbox.write_state(json!({
+        "fake:vim": { "explicit": true, "memo": null },
+        "fake:firefox": { "explicit": true, "memo": null },
+    }));
+    sandbox.set_failing(&["vim"]);
+
+    let output = sandbox.run(&["apply", "--yes"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("1 of 1 batches did not complete"));
+    let step = &sandbox.journal_entry(1)["steps"][0];
+    assert_eq!(step["result"], "failed");
+    assert!(step["error"]
+        .as_str()
+        .unwrap()
+        .contains("vim is set to fail"));
+}
+
+#[test]
+fn rollback_refuses_to_remove_protected_packages() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "fake:sudo": { "explicit": true, "memo": null },
+        "fake:neovim": { "explicit": true, "memo": null },
+    }));
+    let output = sandbox.run(&["apply", "--with-install", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = sandbox.run(&["rollback", "--yes"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The rollback removes protected packages: sudo"));
+    assert_eq!(sandbox.operations().len(), 1);
+}
+
+#[test]
+fn rollback_undoes_the_latest_apply() {
+    let sandbox = synced_sandbox();
+
+    let output = sandbox.run(&["rollback", "--yes"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.operations()[4..],
+        [
+            ("install".to_string(), strings(&["chromium"])),
+            ("remove".to_string(), strings(&["neovim"])),
+            ("mark_as_explicit".to_string(), strings(&["gtk3"])),
+            ("mark_as_deps".to_string(), strings(&["vim"])),
+        ]
+    );
+    let system = sandbox.system();
+    assert_eq!(system["explicit"], json!(["chromium", "gtk3"]));
+    assert_eq!(system["deps"], json!(["vim"]));
+    assert_eq!(sandbox.journal_entry(2)["command"], "rollback 1");
+}
+
+#[test]
+fn rollback_takes_an_entry_id() {
+    let sandbox = synced_sandbox();
+    let output = sandbox.run(&["rollback", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Rolling back the rollback applies the original changes again.
+    let output = sandbox.run(&["rollback", "2", "--dry-run"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(common::stdout(&output).contains("Install (1):\n  neovim\n"));
+    assert_eq!(sandbox.operations().len(), 8);
+}
+
+#[test]
+fn rollback_refuses_when_the_system_has_changed_since() {
+    let sandbox = synced_sandbox();
+    let mut system = sandbox.system();
+    system["explicit"] = json!(["vim"]);
+    std::fs::write(sandbox.system_path(), system.to_string()).unwrap();
+
+    let output = sandbox.run(&["rollback", "--yes"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("neovim (remove): no longer installed"));
+}
+
+#[test]
+fn rollback_without_a_journal_fails() {
+    let sandbox = Sandbox::new(&[], &[]);
+
+    let output = sandbox.run(&["rollback", "--yes"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("The journal is empty"));
+}
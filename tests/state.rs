@@ -0,0 +1,163 @@
+mod common;
+
+use common::{fixture_path, stderr, stdout, Sandbox, STATE_VERSION};
+use serde_json::json;
+use std::fs::{self, File};
+use std::process::Output;
+
+// Entries from before namespacing belong to pacman, so they are queried through the
+// pacman-db fixture.
+fn query_pacman(sandbox: &Sandbox) -> Output {
+    sandbox
+        .bare_command()
+        .args(["--backend", "pacman-db", "--dbpath"])
+        .arg(fixture_path("pacman"))
+        .arg("query")
+        .output()
+        .expect("run moree")
+}
+
+#[test]
+fn unversioned_state_files_are_upgraded_on_save() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_raw_state(json!({
+        "fake:firefox": { "explicit": true, "memo": "browser" },
+        "git": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["add"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        sandbox.read_raw_state(),
+        json!({
+            "version": STATE_VERSION,
+            "packages": {
+                "fake:firefox": { "explicit": true, "memo": "browser" },
+                "pacman:git": { "explicit": true, "memo": null },
+            },
+        })
+    );
+}
+
+#[test]
+fn read_only_commands_leave_old_files_alone() {
+    let sandbox = Sandbox::new(&[], &[]);
+    let legacy = json!({ "firefox": { "explicit": true, "memo": null } });
+    sandbox.write_raw_state(legacy.clone());
+
+    let output = query_pacman(&sandbox);
+
+    assert_eq!(stdout(&output), "firefox\n");
+    assert_eq!(sandbox.read_raw_state(), legacy);
+}
+
+#[test]
+fn a_package_named_version_is_not_mistaken_for_a_schema_version() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_raw_state(json!({ "version": { "explicit": true, "memo": null } }));
+
+    let output = query_pacman(&sandbox);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "version\n");
+}
+
+#[test]
+fn refuses_files_from_a_newer_moree() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    let future = json!({
+        "version": STATE_VERSION + 1,
+        "packages": { "fake:firefox": { "explicit": true, "memo": null } },
+    });
+    sandbox.write_raw_state(future.clone());
+
+    let output = sandbox.run(&["add"]);
+
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("Please upgrade moree"));
+    assert_eq!(sandbox.read_raw_state(), future);
+}
+
+#[test]
+fn rejects_schema_versions_below_one() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_raw_state(json!({ "version": 0, "packages": {} }));
+
+    let output = sandbox.run(&["query"]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(stderr(&output).contains("invalid schema version 0"));
+}
+
+#[test]
+fn wrapped_version_one_files_are_migrated() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_raw_state(json!({
+        "version": 1,
+        "packages": { "firefox": { "explicit": true, "memo": null } },
+    }));
+
+    let output = query_pacman(&sandbox);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "firefox\n");
+}
+
+#[test]
+fn saving_leaves_no_temporary_files_behind() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_state(json!({ "fake:firefox": { "explicit": true, "memo": null } }));
+
+    let output = sandbox.run(&["add"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    let leftovers: Vec<_> = fs::read_dir(sandbox.dir())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.contains(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
+
+#[test]
+fn saving_through_a_symlink_keeps_the_link() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    let target = sandbox.dir().join("dotfiles-state.json");
+    fs::write(
+        &target,
+        json!({
+            "version": STATE_VERSION,
+            "packages": { "fake:firefox": { "explicit": true, "memo": null } },
+        })
+        .to_string(),
+    )
+    .unwrap();
+    std::os::unix::fs::symlink(&target, sandbox.state_path()).unwrap();
+
+    let output = sandbox.run(&["add"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(fs::symlink_metadata(sandbox.state_path())
+        .unwrap()
+        .file_type()
+        .is_symlink());
+}
+
+#[test]
+fn concurrent_writers_fail_cleanly() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    let state = json!({ "fake:firefox": { "explicit": true, "memo": null } });
+    sandbox.write_state(state.clone());
+    let lock = File::create(sandbox.lock_path()).unwrap();
+    lock.lock_shared().unwrap();
+
+    let output = sandbox.run(&["add"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("in use by another moree process"));
+    assert_eq!(sandbox.read_state(), state);
+
+    // Readers can share the lock.
+    let output = sandbox.run(&["query"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+#[test]
+fn counts_packages_per_tag() {
+    let sandbox = Sandbox::new(&[], &[]);
+    sandbox.write_state(json!({
+        "fake:neovim": { "explicit": true, "memo": null, "tags": ["dev", "work"] },
+        "fake:firefox": { "explicit": true, "memo": null, "tags": ["work"] },
+        "fake:gtk3": { "explicit": false, "memo": null },
+        "cargo:ripgrep": { "explicit": true, "memo": null, "tags": ["cli"] },
+    }));
+
+    let output = sandbox.run(&["tags"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "dev   1\nwork  2\n");
+}
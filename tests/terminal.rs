@@ -0,0 +1,73 @@
+mod common;
+
+use common::{stderr, Sandbox};
+use serde_json::json;
+use std::process::Output;
+
+// Without a terminal moree has to fail with an error message, never a panic.
+fn assert_fails_cleanly(output: &Output, expected: &str) {
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr(output);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(
+        stderr.contains("Not running in an interactive terminal"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains(expected), "{}", stderr);
+}
+
+#[test]
+fn add_points_to_the_flags_when_it_would_prompt() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+
+    let output = sandbox.run(&["add", "firefox"]);
+
+    assert_fails_cleanly(&output, "use --explicit or --dep");
+    assert!(!sandbox.has_state());
+}
+
+#[test]
+fn edit_fails_without_a_terminal() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["edit", "firefox"]);
+
+    assert_fails_cleanly(&output, "--explicit|--dep --memo");
+}
+
+#[test]
+fn apply_needs_yes_without_a_terminal() {
+    let sandbox = Sandbox::new(&["firefox", "gtk3"], &[]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+        "fake:gtk3": { "explicit": false, "memo": null },
+    }));
+
+    let output = sandbox.run(&["apply"]);
+
+    assert_fails_cleanly(&output, "pass --yes");
+    assert!(sandbox.operations().is_empty());
+}
+
+#[test]
+fn state_path_without_json_extension_is_refused_without_a_terminal() {
+    let sandbox = Sandbox::new(&["firefox"], &[]);
+    let state_path = sandbox.dir().join("state.txt");
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_moree"))
+        .env("XDG_CONFIG_HOME", sandbox.dir().join("config"))
+        .env(moree::package_manager_integration::FAKE_BACKEND_VAR, "1")
+        .arg("--data")
+        .arg(&state_path)
+        .args(["--backend", "fake", "--dbpath"])
+        .arg(sandbox.system_path())
+        .args(["query", "-e"])
+        .output()
+        .unwrap();
+
+    assert_fails_cleanly(&output, "does not have a .json extension");
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+#[test]
+fn lists_explicit_packages_missing_from_state() {
+    let sandbox = Sandbox::new(&["zsh", "firefox", "chromium"], &["gtk3"]);
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": null },
+    }));
+
+    let output = sandbox.run(&["unmanaged"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "chromium\nzsh\n");
+}
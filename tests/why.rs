@@ -0,0 +1,70 @@
+mod common;
+
+use common::{stderr, stdout, Sandbox};
+use serde_json::json;
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new(&["firefox", "gimp"], &["gtk3", "glib2", "libpng"]);
+    sandbox.set_depends(json!({
+        "firefox": ["gtk3"],
+        "gimp": ["gtk3", "libpng"],
+        "gtk3": ["glib2"],
+    }));
+    sandbox.write_state(json!({
+        "fake:firefox": { "explicit": true, "memo": "browser" },
+        "fake:gtk3": { "explicit": false, "memo": "toolkit" },
+    }));
+    sandbox
+}
+
+#[test]
+fn traces_dependencies_to_the_explicit_packages_that_need_them() {
+    let output = sandbox().run(&["why", "glib2"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+glib2 is installed as a dependency.
+Required by:
+  firefox -> gtk3 -> glib2
+    firefox: browser
+  gimp -> gtk3 -> glib2
+    gimp: no memo recorded
+"
+    );
+}
+
+#[test]
+fn shows_the_memo_of_the_package_itself() {
+    let sandbox = sandbox();
+
+    let output = sandbox.run(&["why", "firefox"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "firefox is explicitly installed.\nMemo: browser\n"
+    );
+
+    let output = sandbox.run(&["why", "gtk3"]);
+    assert!(stdout(&output).starts_with("gtk3 is installed as a dependency.\nMemo: toolkit\n"));
+}
+
+#[test]
+fn reports_dependencies_nothing_requires() {
+    let sandbox = sandbox();
+    sandbox.set_depends(json!({}));
+
+    let output = sandbox.run(&["why", "libpng"]);
+
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Nothing explicitly installed requires it."));
+}
+
+#[test]
+fn rejects_unknown_packages() {
+    let output = sandbox().run(&["why", "chromium"]);
+
+    assert_eq!(output.status.code(), Some(6));
+    assert!(stderr(&output).contains("chromium"));
+}